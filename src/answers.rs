@@ -0,0 +1,93 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+use crate::solution::Answer;
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail(Answer),
+    Unknown,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Pass => write!(f, "PASS"),
+            Status::Fail(expected) => write!(f, "FAIL (expected {})", expected),
+            Status::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+// known answers of a single day, `answers/dayN.txt` holds one line per part,
+// an empty line leaves that part unknown
+#[derive(Debug, PartialEq)]
+pub struct Answers {
+    parts: Vec<Option<Answer>>,
+}
+
+impl Answers {
+    pub fn load(day: u8) -> Result<Answers> {
+        let path = PathBuf::from(format!("./answers/day{}.txt", day));
+        match fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Answers { parts: Vec::new() }),
+            Err(source) => Err(Error::Io { line: None, source }),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Answers> {
+        let mut parts: Vec<Option<Answer>> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                parts.push(None);
+                continue;
+            }
+
+            let answer = trimmed.parse::<Answer>().map_err(|_| {
+                let column = line.len() - line.trim_start().len() + 1;
+                Error::parse(i + 1, column, trimmed, "bad answer")
+            })?;
+            parts.push(Some(answer));
+        }
+
+        Ok(Answers { parts })
+    }
+
+    pub fn check(&self, part: u8, answer: Answer) -> Status {
+        match self.parts.get(part as usize - 1) {
+            Some(Some(expected)) if *expected == answer => Status::Pass,
+            Some(Some(expected)) => Status::Fail(*expected),
+            _ => Status::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let answers = Answers::parse("142\n").unwrap();
+        assert_eq!(answers.check(1, 142), Status::Pass);
+        assert_eq!(answers.check(1, 141), Status::Fail(142));
+        assert_eq!(answers.check(2, 281), Status::Unknown);
+
+        let answers = Answers::parse("\n281\n").unwrap();
+        assert_eq!(answers.check(1, 142), Status::Unknown);
+        assert_eq!(answers.check(2, 281), Status::Pass);
+    }
+
+    #[test]
+    fn bad_answer() {
+        let err = Answers::parse("142\n  28x\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 3: bad answer: \"28x\"");
+    }
+}
@@ -0,0 +1,645 @@
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "usage:
+    aoc2023 run --day <N> --part <1|2> [--input <path|-> | --example]
+    aoc2023 all
+    aoc2023 reverse day5 --location <N|N..M> [--input <path|->]
+    aoc2023 convert day5 --from <category> --to <category> [--input <path|->]
+    aoc2023 check day5 [--input <path|->]
+    aoc2023 brute-force day5 [--threads <N>] [--progress] [--input <path|->]
+    aoc2023 calibrate day1 [--part <1|2>] [--vocabulary <path>] [--words <token=digit,...>]
+                           [--skip-empty] [--explain] [--input <path|->]
+    aoc2023 bench day1 [--lines <N>] [--length <N>] [--spacing <N>]
+    aoc2023 possible day2 [--bag <color=count,...>] [--input <path|->]
+    aoc2023 report day2 [--bag <color=count,...>] [--input <path|->]
+    aoc2023 infer day2 [--bag <color=count,...>] [--max-count <N>] [--input <path|->]";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run {
+        day: u8,
+        part: u8,
+        input: Option<PathBuf>,
+        example: bool,
+    },
+    All,
+    Reverse {
+        locations: Range<i64>,
+        input: Option<PathBuf>,
+    },
+    Convert {
+        from: String,
+        to: String,
+        input: Option<PathBuf>,
+    },
+    Check {
+        input: Option<PathBuf>,
+    },
+    BruteForce {
+        threads: Option<usize>,
+        progress: bool,
+        input: Option<PathBuf>,
+    },
+    // the vocabulary file replaces the english words, --words adds to either
+    Calibrate {
+        part: u8,
+        vocabulary: Option<PathBuf>,
+        words: Vec<(String, u32)>,
+        skip_empty: bool,
+        explain: bool,
+        input: Option<PathBuf>,
+    },
+    Bench {
+        lines: usize,
+        length: usize,
+        spacing: u64,
+    },
+    Possible {
+        bag: Option<Vec<(String, u32)>>,
+        input: Option<PathBuf>,
+    },
+    Report {
+        bag: Option<Vec<(String, u32)>>,
+        input: Option<PathBuf>,
+    },
+    Infer {
+        bag: Option<Vec<(String, u32)>>,
+        max_count: u32,
+        input: Option<PathBuf>,
+    },
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+
+    match args.next().map(|s| s.as_str()) {
+        Some("run") => parse_run(args),
+        Some("all") => match args.next() {
+            Some(other) => Err(format!("unknown argument '{}'", other)),
+            None => Ok(Command::All),
+        },
+        Some("reverse") => {
+            expect_day("reverse", "day5", args.next())?;
+            parse_reverse(args)
+        }
+        Some("convert") => {
+            expect_day("convert", "day5", args.next())?;
+            parse_convert(args)
+        }
+        Some("check") => {
+            expect_day("check", "day5", args.next())?;
+            parse_check(args)
+        }
+        Some("brute-force") => {
+            expect_day("brute-force", "day5", args.next())?;
+            parse_brute_force(args)
+        }
+        Some("calibrate") => {
+            expect_day("calibrate", "day1", args.next())?;
+            parse_calibrate(args)
+        }
+        Some("bench") => {
+            expect_day("bench", "day1", args.next())?;
+            parse_bench(args)
+        }
+        Some("possible") => {
+            expect_day("possible", "day2", args.next())?;
+            let (bag, input) = parse_bag(args, unknown_argument)?;
+            Ok(Command::Possible { bag, input })
+        }
+        Some("report") => {
+            expect_day("report", "day2", args.next())?;
+            let (bag, input) = parse_bag(args, unknown_argument)?;
+            Ok(Command::Report { bag, input })
+        }
+        Some("infer") => {
+            expect_day("infer", "day2", args.next())?;
+            parse_infer(args)
+        }
+        Some(other) => Err(format!("unknown command '{}'", other)),
+        None => Err("missing command".to_string()),
+    }
+}
+
+fn expect_day(command: &str, day: &str, value: Option<&String>) -> Result<(), String> {
+    match value {
+        Some(value) if value == day => Ok(()),
+        _ => Err(format!("{} is only available for {}", command, day)),
+    }
+}
+
+fn parse_run(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut day: Option<u8> = None;
+    let mut part: Option<u8> = None;
+    let mut input: Option<PathBuf> = None;
+    let mut example = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" | "-d" => day = Some(parse_value("--day", args.next())?),
+            "--part" | "-p" => part = Some(parse_value("--part", args.next())?),
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            "--example" | "-e" => example = true,
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if example && input.is_some() {
+        return Err("--example and --input can't be used together".to_string());
+    }
+
+    Ok(Command::Run {
+        day: day.ok_or("missing --day")?,
+        part: part.ok_or("missing --part")?,
+        input,
+        example,
+    })
+}
+
+fn parse_reverse(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut locations: Option<Range<i64>> = None;
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--location" | "-l" => {
+                let value = args.next().ok_or("missing value for --location")?;
+                locations = Some(parse_locations(value)?);
+            }
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    let locations = locations.ok_or("missing --location")?;
+    if locations.is_empty() {
+        return Err(format!("empty location range {:?}", locations));
+    }
+
+    Ok(Command::Reverse { locations, input })
+}
+
+fn parse_convert(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut from: Option<String> = None;
+    let mut to: Option<String> = None;
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = Some(args.next().ok_or("missing value for --from")?.clone()),
+            "--to" => to = Some(args.next().ok_or("missing value for --to")?.clone()),
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(Command::Convert {
+        from: from.ok_or("missing --from")?,
+        to: to.ok_or("missing --to")?,
+        input,
+    })
+}
+
+fn parse_check(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(Command::Check { input })
+}
+
+fn parse_brute_force(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut threads: Option<usize> = None;
+    let mut progress = false;
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" | "-t" => threads = Some(parse_value("--threads", args.next())?),
+            "--progress" => progress = true,
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if threads == Some(0) {
+        return Err("--threads must be at least 1".to_string());
+    }
+
+    Ok(Command::BruteForce {
+        threads,
+        progress,
+        input,
+    })
+}
+
+fn parse_calibrate(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut part: u8 = 2;
+    let mut vocabulary: Option<PathBuf> = None;
+    let mut words: Vec<(String, u32)> = Vec::new();
+    let mut skip_empty = false;
+    let mut explain = false;
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" | "-p" => part = parse_value("--part", args.next())?,
+            "--vocabulary" => {
+                vocabulary = Some(PathBuf::from(
+                    args.next().ok_or("missing value for --vocabulary")?,
+                ))
+            }
+            "--words" | "-w" => {
+                let pairs = parse_pairs("--words", args.next())?;
+                if let Some((token, value)) = pairs.iter().find(|(_, value)| *value > 9) {
+                    return Err(format!(
+                        "bad value for --words: '{}={}' is not a digit 0-9",
+                        token, value
+                    ));
+                }
+                words.extend(pairs)
+            }
+            "--skip-empty" => skip_empty = true,
+            "--explain" => explain = true,
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if !(1..=2).contains(&part) {
+        return Err(format!("bad value for --part: '{}'", part));
+    }
+    // part 1 only reads the literal digits
+    if part == 1 && (vocabulary.is_some() || !words.is_empty()) {
+        return Err("--vocabulary and --words need --part 2".to_string());
+    }
+
+    Ok(Command::Calibrate {
+        part,
+        vocabulary,
+        words,
+        skip_empty,
+        explain,
+        input,
+    })
+}
+
+fn parse_bench(mut args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut lines: usize = 1000;
+    let mut length: usize = 10000;
+    let mut spacing: u64 = 100;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lines" => lines = parse_value("--lines", args.next())?,
+            "--length" => length = parse_value("--length", args.next())?,
+            "--spacing" => spacing = parse_value("--spacing", args.next())?,
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if spacing == 0 {
+        return Err("--spacing must be at least 1".to_string());
+    }
+
+    Ok(Command::Bench {
+        lines,
+        length,
+        spacing,
+    })
+}
+
+fn parse_infer(args: std::slice::Iter<String>) -> Result<Command, String> {
+    let mut max_count: u32 = 20;
+    let (bag, input) = parse_bag(args, |arg, args| match arg {
+        "--max-count" => {
+            max_count = parse_value("--max-count", args.next())?;
+            Ok(())
+        }
+        other => unknown_argument(other, args),
+    })?;
+
+    Ok(Command::Infer {
+        bag,
+        max_count,
+        input,
+    })
+}
+
+type BagArgs = (Option<Vec<(String, u32)>>, Option<PathBuf>);
+
+// --bag and --input, the other flags of the command go to `parse_other`
+fn parse_bag<'a>(
+    mut args: std::slice::Iter<'a, String>,
+    mut parse_other: impl FnMut(&str, &mut std::slice::Iter<'a, String>) -> Result<(), String>,
+) -> Result<BagArgs, String> {
+    let mut bag: Option<Vec<(String, u32)>> = None;
+    let mut input: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bag" | "-b" => bag = Some(parse_pairs("--bag", args.next())?),
+            "--input" | "-i" => input = Some(parse_input(args.next())?),
+            other => parse_other(other, &mut args)?,
+        }
+    }
+
+    Ok((bag, input))
+}
+
+// "name=value,name=value"
+fn parse_pairs(flag: &str, value: Option<&String>) -> Result<Vec<(String, u32)>, String> {
+    let value = value.ok_or(format!("missing value for {}", flag))?;
+    let bad_value = || format!("bad value for {}: '{}'", flag, value);
+
+    value
+        .split(',')
+        .map(|word| {
+            let (token, digit) = word.split_once('=').ok_or_else(bad_value)?;
+            if token.is_empty() {
+                return Err(bad_value());
+            }
+            Ok((token.to_string(), digit.parse().map_err(|_| bad_value())?))
+        })
+        .collect()
+}
+
+// "N" is the single location N, "N..M" is the half-open range
+fn parse_locations(value: &str) -> Result<Range<i64>, String> {
+    let bad_value = |_| format!("bad value for --location: '{}'", value);
+
+    match value.split_once("..") {
+        Some((start, end)) => {
+            Ok(start.parse::<i64>().map_err(bad_value)?..end.parse::<i64>().map_err(bad_value)?)
+        }
+        None => {
+            let location = value.parse::<i64>().map_err(bad_value)?;
+            let end = location
+                .checked_add(1)
+                .ok_or_else(|| format!("bad value for --location: '{}'", value))?;
+            Ok(location..end)
+        }
+    }
+}
+
+fn unknown_argument(arg: &str, _: &mut std::slice::Iter<String>) -> Result<(), String> {
+    Err(format!("unknown argument '{}'", arg))
+}
+
+fn parse_input(value: Option<&String>) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(value.ok_or("missing value for --input")?))
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or(format!("missing value for {}", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("bad value for {}: '{}'", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn run() {
+        let command = parse_args(&args("run --day 3 --part 2"));
+        assert_eq!(
+            command,
+            Ok(Command::Run {
+                day: 3,
+                part: 2,
+                input: None,
+                example: false
+            })
+        );
+
+        let command = parse_args(&args("run -p 1 -d 5 --input in.txt"));
+        assert_eq!(
+            command,
+            Ok(Command::Run {
+                day: 5,
+                part: 1,
+                input: Some(PathBuf::from("in.txt")),
+                example: false
+            })
+        );
+
+        let command = parse_args(&args("run --day 2 --part 1 --example"));
+        assert_eq!(
+            command,
+            Ok(Command::Run {
+                day: 2,
+                part: 1,
+                input: None,
+                example: true
+            })
+        );
+    }
+
+    #[test]
+    fn all() {
+        assert_eq!(parse_args(&args("all")), Ok(Command::All));
+    }
+
+    #[test]
+    fn reverse() {
+        assert_eq!(
+            parse_args(&args("reverse day5 --location 46")),
+            Ok(Command::Reverse {
+                locations: 46..47,
+                input: None
+            })
+        );
+        assert_eq!(
+            parse_args(&args("reverse day5 -l 40..60 -i -")),
+            Ok(Command::Reverse {
+                locations: 40..60,
+                input: Some(PathBuf::from("-"))
+            })
+        );
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(
+            parse_args(&args("convert day5 --from soil --to humidity")),
+            Ok(Command::Convert {
+                from: "soil".to_string(),
+                to: "humidity".to_string(),
+                input: None
+            })
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(
+            parse_args(&args("check day5")),
+            Ok(Command::Check { input: None })
+        );
+        assert_eq!(
+            parse_args(&args("check day5 -i in.txt")),
+            Ok(Command::Check {
+                input: Some(PathBuf::from("in.txt"))
+            })
+        );
+    }
+
+    #[test]
+    fn brute_force() {
+        assert_eq!(
+            parse_args(&args("brute-force day5")),
+            Ok(Command::BruteForce {
+                threads: None,
+                progress: false,
+                input: None
+            })
+        );
+        assert_eq!(
+            parse_args(&args("brute-force day5 --progress -t 8 -i -")),
+            Ok(Command::BruteForce {
+                threads: Some(8),
+                progress: true,
+                input: Some(PathBuf::from("-"))
+            })
+        );
+    }
+
+    #[test]
+    fn calibrate() {
+        assert_eq!(
+            parse_args(&args("calibrate day1")),
+            Ok(Command::Calibrate {
+                part: 2,
+                vocabulary: None,
+                words: vec![],
+                skip_empty: false,
+                explain: false,
+                input: None
+            })
+        );
+        assert_eq!(
+            parse_args(&args(
+                "calibrate day1 --vocabulary fr.txt -w zero=0,neuf=9 -w nul=0 --skip-empty --explain"
+            )),
+            Ok(Command::Calibrate {
+                part: 2,
+                vocabulary: Some(PathBuf::from("fr.txt")),
+                words: vec![
+                    ("zero".to_string(), 0),
+                    ("neuf".to_string(), 9),
+                    ("nul".to_string(), 0)
+                ],
+                skip_empty: true,
+                explain: true,
+                input: None
+            })
+        );
+        assert_eq!(
+            parse_args(&args("calibrate day1 -p 1 --skip-empty --explain -i -")),
+            Ok(Command::Calibrate {
+                part: 1,
+                vocabulary: None,
+                words: vec![],
+                skip_empty: true,
+                explain: true,
+                input: Some(PathBuf::from("-"))
+            })
+        );
+    }
+
+    #[test]
+    fn bench() {
+        assert_eq!(
+            parse_args(&args("bench day1 --lines 10 --spacing 5")),
+            Ok(Command::Bench {
+                lines: 10,
+                length: 10000,
+                spacing: 5
+            })
+        );
+    }
+
+    #[test]
+    fn possible() {
+        assert_eq!(
+            parse_args(&args("possible day2")),
+            Ok(Command::Possible {
+                bag: None,
+                input: None
+            })
+        );
+        assert_eq!(
+            parse_args(&args("possible day2 --bag red=12,yellow=3")),
+            Ok(Command::Possible {
+                bag: Some(vec![("red".to_string(), 12), ("yellow".to_string(), 3)]),
+                input: None
+            })
+        );
+    }
+
+    #[test]
+    fn report() {
+        assert_eq!(
+            parse_args(&args("report day2 -b blue=1 -i -")),
+            Ok(Command::Report {
+                bag: Some(vec![("blue".to_string(), 1)]),
+                input: Some(PathBuf::from("-"))
+            })
+        );
+    }
+
+    #[test]
+    fn infer() {
+        assert_eq!(
+            parse_args(&args("infer day2 --max-count 30")),
+            Ok(Command::Infer {
+                bag: None,
+                max_count: 30,
+                input: None
+            })
+        );
+    }
+
+    #[test]
+    fn bad_args() {
+        assert!(parse_args(&args("")).is_err());
+        assert!(parse_args(&args("walk --day 1 --part 1")).is_err());
+        assert!(parse_args(&args("run --day 1")).is_err());
+        assert!(parse_args(&args("run --day x --part 1")).is_err());
+        assert!(parse_args(&args("run --day 1 --part")).is_err());
+        assert!(parse_args(&args("run --day 1 --part 1 --verbose")).is_err());
+        assert!(parse_args(&args("all --day 1")).is_err());
+        assert!(parse_args(&args("reverse day4 --location 1")).is_err());
+        assert!(parse_args(&args("reverse day5")).is_err());
+        assert!(parse_args(&args("reverse day5 --location 5..5")).is_err());
+        assert!(parse_args(&args("reverse day5 --location 1..x")).is_err());
+        assert!(parse_args(&args("reverse day5 --location 9223372036854775807")).is_err());
+        assert!(parse_args(&args("convert day5 --from soil")).is_err());
+        assert!(parse_args(&args("check day2")).is_err());
+        assert!(parse_args(&args("brute-force day5 --threads 0")).is_err());
+        assert!(parse_args(&args("calibrate day1 --words zero")).is_err());
+        assert!(parse_args(&args("bench day1 --spacing 0")).is_err());
+        assert!(parse_args(&args("possible day2 --bag red:12")).is_err());
+        assert!(parse_args(&args("report day5")).is_err());
+        assert!(parse_args(&args("infer day2 --max-count -1")).is_err());
+        assert!(parse_args(&args("calibrate day1 --words =0")).is_err());
+        assert!(parse_args(&args("calibrate day1 --words zero=x")).is_err());
+        assert!(parse_args(&args("calibrate day1 --words big=500000000")).is_err());
+        assert!(parse_args(&args("calibrate day1 --part 3")).is_err());
+        assert!(parse_args(&args("calibrate day1 --part 1 --words nul=0")).is_err());
+        assert!(parse_args(&args("run --day 1 --part 1 --example -i in.txt")).is_err());
+    }
+}
@@ -1,92 +1,609 @@
-use std::fs::File;
-use std::io;
+use std::collections::{HashMap, VecDeque};
 use std::io::BufRead;
+use std::str::Bytes;
+use std::time::{Duration, Instant};
 
-pub fn day1_part1(file: &mut io::BufReader<File>) -> u32 {
+use crate::error::{column_at, Error, Result};
+use crate::solution::{read_lines, Answer, Example, Solution};
+
+const EXAMPLE_PART1: &str = "\
+1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet
+";
+
+const EXAMPLE_PART2: &str = "\
+two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen
+";
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<String>;
+
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            part: 1,
+            input: EXAMPLE_PART1,
+            answer: 142,
+        },
+        Example {
+            part: 2,
+            input: EXAMPLE_PART2,
+            answer: 281,
+        },
+    ];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input> {
+        read_lines(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(day1_part1(input, false)?.into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(day1_part2(input, &Vocabulary::english(), false)?.into())
+    }
+}
+
+pub fn day1_part1(lines: &[String], skip_empty: bool) -> Result<u32> {
     let mut sum: u32 = 0;
 
-    for line in file.lines() {
-        let mut first: u32 = 0;
-        let mut last: u32 = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let mut first: Option<u32> = None;
+        let mut last: Option<u32> = None;
 
-        for c in line.unwrap().chars() {
-            if c.is_digit(10) {
-                let digit = c.to_digit(10).unwrap();
-                if first == 0 {
-                    first = digit;
-                }
-                last = digit;
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                first = first.or(Some(digit));
+                last = Some(digit);
             }
         }
 
-        sum += first * 10 + last;
+        sum += line_value(i + 1, line, first.zip(last), skip_empty)?;
     }
 
-    sum
+    Ok(sum)
 }
 
-struct Digits {
-    strings: [&'static str; 9],
-    counters: [u8; 9],
+// lines without digits count as 0 when skipped
+fn line_value(
+    line_no: usize,
+    line: &str,
+    digits: Option<(u32, u32)>,
+    skip_empty: bool,
+) -> Result<u32> {
+    match digits {
+        Some((first, last)) => Ok(first * 10 + last),
+        None if skip_empty => Ok(0),
+        None => Err(Error::semantic(line_no, 1, line, "no digits in line")),
+    }
 }
 
-impl Digits {
-    fn new() -> Digits {
-        Digits {
-            strings: [
-                "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-            ],
-            counters: [0; 9],
+const WORDS: [(&str, u32); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+// spelled out tokens recognized next to the literal digits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    words: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    // no words at all, only the literal digits of part 1
+    pub fn digits() -> Vocabulary {
+        Vocabulary { words: Vec::new() }
+    }
+
+    pub fn english() -> Vocabulary {
+        Vocabulary {
+            words: WORDS.map(|(w, d)| (w.to_string(), d)).to_vec(),
         }
     }
 
-    fn advance_counters(&mut self, c: char) -> Option<u8> {
-        let mut res: Option<u8> = None;
+    // one "<token>=<digit>" per line, blank lines and '#' comments are skipped
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Result<Vocabulary> {
+        let mut words: Vec<(String, u32)> = Vec::new();
 
-        for i in 0..self.counters.len() {
-            let digit_chars: Vec<_> = self.strings[i].chars().collect();
-            if c == digit_chars[self.counters[i] as usize] {
-                self.counters[i] += 1;
-                if self.counters[i] == digit_chars.len() as u8 {
-                    self.counters[i] = 0;
-                    res = Some((i + 1) as u8)
-                }
-            } else {
-                self.counters[i] = if c == digit_chars[0] { 1 } else { 0 };
+        for (i, line) in lines.iter().enumerate() {
+            let line = line.as_ref();
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let (token, value) = line
+                .split_once('=')
+                .ok_or_else(|| Error::parse(i + 1, 1, line, "expected '<token>=<value>'"))?;
+            let token = token.trim();
+            if token.is_empty() {
+                return Err(Error::parse(i + 1, 1, line, "empty token"));
+            }
+
+            let value_offset = line.len() - value.len();
+            let value = value.trim().parse::<u32>().map_err(|_| {
+                Error::parse(
+                    i + 1,
+                    column_at(line, value_offset),
+                    line,
+                    "expected a number",
+                )
+            })?;
+            if value > 9 {
+                return Err(Error::parse(
+                    i + 1,
+                    column_at(line, value_offset),
+                    line,
+                    "expected a digit 0-9",
+                ));
+            }
+
+            words.push((token.to_string(), value));
+        }
+
+        Ok(Vocabulary { words })
+    }
+
+    pub fn extend(&mut self, words: Vec<(String, u32)>) {
+        self.words.extend(words);
+    }
+
+    // the digits 0-9 and the tokens of the vocabulary
+    fn patterns(&self) -> Vec<(Vec<u8>, u32)> {
+        let digits: Vec<(String, u32)> = (0..=9).map(|d| (d.to_string(), d)).collect();
+        digits
+            .iter()
+            .chain(self.words.iter())
+            .map(|(w, d)| (w.as_bytes().to_vec(), *d))
+            .collect()
+    }
+
+    pub fn matcher(&self) -> Matcher {
+        Matcher::new(self.patterns())
+    }
+
+    pub fn scanner(&self) -> Scanner {
+        let reversed = self.patterns().into_iter().map(|(mut pattern, value)| {
+            pattern.reverse();
+            (pattern, value)
+        });
+
+        Scanner {
+            forward: self.matcher(),
+            backward: Matcher::new(reversed),
+        }
+    }
+}
+
+// finds the first match scanning forward and the last one scanning backward, so
+// only the ends of a line are read. "first" is the match that ends first and
+// "last" the one that starts last, which is the same as the first and the last
+// of a full forward scan unless a token contains another one.
+pub struct Scanner {
+    forward: Matcher,
+    backward: Matcher,
+}
+
+impl Scanner {
+    pub fn calibration(&self, line: &str) -> Option<(u32, u32)> {
+        let first = self.forward.matches(line).next()?;
+        let last = self.backward.scan(line.bytes().rev()).next()?;
+
+        Some((first.value, last.value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub value: u32,
+}
+
+#[derive(Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    // (length, value) of every pattern ending here, longest first
+    outputs: Vec<(usize, u32)>,
+}
+
+// Aho-Corasick automaton over the bytes of the patterns
+pub struct Matcher {
+    nodes: Vec<Node>,
+}
+
+impl Matcher {
+    pub fn new<S, I>(patterns: I) -> Matcher
+    where
+        S: AsRef<[u8]>,
+        I: IntoIterator<Item = (S, u32)>,
+    {
+        let mut nodes: Vec<Node> = vec![Node::default()];
+
+        for (pattern, value) in patterns {
+            let pattern = pattern.as_ref();
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut state = 0;
+            for &b in pattern {
+                state = match nodes[state].next.get(&b) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(b, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].outputs.push((pattern.len(), value));
+        }
+
+        // breadth first, so the fail target of a node is complete before the node itself
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let children: Vec<(u8, usize)> =
+                nodes[state].next.iter().map(|(&b, &n)| (b, n)).collect();
+
+            for (b, child) in children {
+                let mut fail = nodes[state].fail;
+                let fail = loop {
+                    match nodes[fail].next.get(&b) {
+                        Some(&next) => break next,
+                        None if fail == 0 => break 0,
+                        None => fail = nodes[fail].fail,
+                    }
+                };
+
+                nodes[child].fail = fail;
+                let inherited = nodes[fail].outputs.clone();
+                nodes[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Matcher { nodes }
+    }
+
+    fn step(&self, mut state: usize, b: u8) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&b) {
+                return next;
             }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
         }
+    }
 
-        res
+    // every match, overlapping ones included, ordered by their end
+    pub fn matches<'a>(&'a self, text: &'a str) -> Matches<'a, Bytes<'a>> {
+        self.scan(text.bytes())
+    }
+
+    // positions are counted in the order the bytes come in
+    pub fn scan<I: Iterator<Item = u8>>(&self, bytes: I) -> Matches<'_, I> {
+        Matches {
+            matcher: self,
+            bytes,
+            pos: 0,
+            state: 0,
+            output: 0,
+        }
+    }
+}
+
+pub struct Matches<'a, I> {
+    matcher: &'a Matcher,
+    bytes: I,
+    pos: usize,
+    state: usize,
+    output: usize,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Matches<'_, I> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            let outputs = &self.matcher.nodes[self.state].outputs;
+            if let Some(&(len, value)) = outputs.get(self.output) {
+                self.output += 1;
+                return Some(Match {
+                    start: self.pos - len,
+                    end: self.pos,
+                    value,
+                });
+            }
+
+            let b = self.bytes.next()?;
+            self.state = self.matcher.step(self.state, b);
+            self.pos += 1;
+            self.output = 0;
+        }
     }
 }
 
-pub fn day1_part2(file: &mut io::BufReader<File>) -> u32 {
+// values of the first and the last match of a full forward scan
+fn calibration(matcher: &Matcher, line: &str) -> Option<(u32, u32)> {
+    let mut matches = matcher.matches(line);
+    let first = matches.next()?;
+    let last = matches.last().unwrap_or(first);
+
+    Some((first.value, last.value))
+}
+
+pub fn day1_part2(lines: &[String], vocabulary: &Vocabulary, skip_empty: bool) -> Result<u32> {
+    let scanner = vocabulary.scanner();
     let mut sum: u32 = 0;
 
-    for line in file.lines() {
-        let mut first: u32 = 0;
-        let mut last: u32 = 0;
-        let mut digit_tracker = Digits::new();
+    for (i, line) in lines.iter().enumerate() {
+        sum += line_value(i + 1, line, scanner.calibration(line), skip_empty)?;
+    }
+
+    Ok(sum)
+}
 
-        for c in line.unwrap().chars() {
-            if c.is_digit(10) {
-                let digit = c.to_digit(10).unwrap();
-                if first == 0 {
-                    first = digit;
-                }
-                last = digit;
-            } else if c.is_alphabetic() {
-                if let Some(digit) = digit_tracker.advance_counters(c) {
-                    if first == 0 {
-                        first = digit as u32;
+pub fn print_explain(lines: &[String], vocabulary: &Vocabulary, skip_empty: bool) -> Result<()> {
+    let scanner = vocabulary.scanner();
+    let mut sum: u32 = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let digits = scanner.calibration(line);
+        let value = line_value(i + 1, line, digits, skip_empty)?;
+        match digits {
+            Some((first, last)) => println!(
+                "line {}: first {}, last {}, value {}",
+                i + 1,
+                first,
+                last,
+                value
+            ),
+            None => println!("line {}: no digits, skipped", i + 1),
+        }
+        sum += value;
+    }
+    println!("sum {}", sum);
+
+    Ok(())
+}
+
+// random lowercase lines with a digit or a spelled digit once in about
+// `spacing` characters
+fn generate(count: usize, length: usize, spacing: u64) -> Vec<String> {
+    // xorshift64
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |n: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % n
+    };
+
+    (0..count)
+        .map(|_| {
+            let mut line = String::with_capacity(length + 8);
+            while line.len() < length {
+                if next(spacing) == 0 {
+                    match next(2) {
+                        0 => line.push(char::from(b'0' + next(10) as u8)),
+                        _ => line.push_str(WORDS[next(9) as usize].0),
                     }
-                    last = digit as u32;
+                } else {
+                    line.push(char::from(b'a' + next(26) as u8));
                 }
             }
-        }
+            line
+        })
+        .collect()
+}
+
+fn time<F: FnMut() -> u64>(mut f: F) -> (u64, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+// compares the bidirectional scan against the full forward scan
+pub fn print_bench(count: usize, length: usize, spacing: u64) -> bool {
+    let lines = generate(count, length, spacing);
+    let vocabulary = Vocabulary::english();
+    let matcher = vocabulary.matcher();
+    let scanner = vocabulary.scanner();
+
+    let sum = |digits: Option<(u32, u32)>| digits.map_or(0, |(first, last)| first * 10 + last);
 
-        sum += first * 10 + last;
+    let (forward, forward_time) = time(|| {
+        lines
+            .iter()
+            .map(|line| u64::from(sum(calibration(&matcher, line))))
+            .sum()
+    });
+    let (bidirectional, bidirectional_time) = time(|| {
+        lines
+            .iter()
+            .map(|line| u64::from(sum(scanner.calibration(line))))
+            .sum()
+    });
+
+    println!(
+        "{} lines of {} characters, a digit every {} characters",
+        count, length, spacing
+    );
+    println!("forward:       {:>12} {:>12.2?}", forward, forward_time);
+    println!(
+        "bidirectional: {:>12} {:>12.2?}",
+        bidirectional, bidirectional_time
+    );
+
+    if forward != bidirectional {
+        println!("MISMATCH");
     }
+    forward == bidirectional
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::check_examples;
+
+    #[test]
+    fn examples() {
+        check_examples::<Day1>();
+    }
+
+    fn values(matcher: &Matcher, text: &str) -> Vec<u32> {
+        matcher.matches(text).map(|m| m.value).collect()
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let matcher = Vocabulary::english().matcher();
+        assert_eq!(values(&matcher, "oneight"), vec![1, 8]);
+        assert_eq!(values(&matcher, "twone3"), vec![2, 1, 3]);
+        assert_eq!(values(&matcher, "sevenine"), vec![7, 9]);
+        assert_eq!(values(&matcher, "ninine"), vec![9]);
+        assert_eq!(values(&matcher, "eeight"), vec![8]);
+        assert_eq!(values(&matcher, "thrthree"), vec![3]);
+        assert_eq!(values(&matcher, "xyz"), vec![]);
+
+        assert_eq!(
+            matcher.matches("zoneight").collect::<Vec<_>>(),
+            vec![
+                Match {
+                    start: 1,
+                    end: 4,
+                    value: 1
+                },
+                Match {
+                    start: 3,
+                    end: 8,
+                    value: 8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_and_missing_digits() {
+        let lines: Vec<String> = ["0abc5", "a0b3", "7"].map(String::from).to_vec();
+        assert_eq!(day1_part1(&lines, false).unwrap(), 5 + 3 + 77);
+        assert_eq!(
+            day1_part2(&lines, &Vocabulary::english(), false).unwrap(),
+            5 + 3 + 77
+        );
 
-    sum
+        let lines: Vec<String> = ["1", "abc", "two"].map(String::from).to_vec();
+        let err = day1_part1(&lines, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: no digits in line: \"abc\""
+        );
+        assert_eq!(day1_part1(&lines, true).unwrap(), 11);
+
+        assert!(day1_part2(&lines, &Vocabulary::english(), false).is_err());
+        assert_eq!(
+            day1_part2(&lines, &Vocabulary::english(), true).unwrap(),
+            11 + 22
+        );
+        // without words the scanner reads the lines like part 1
+        assert_eq!(
+            day1_part2(&lines, &Vocabulary::digits(), true).unwrap(),
+            day1_part1(&lines, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let vocabulary =
+            Vocabulary::from_lines(&["# french", "un=1", "deux = 2", "", "trois=3", "quatre=4"])
+                .unwrap();
+        let lines: Vec<String> = ["xundeuxtrois", "quatre5un", "onetwo7"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            day1_part2(&lines, &vocabulary, false).unwrap(),
+            13 + 41 + 77
+        );
+
+        let mut vocabulary = Vocabulary::english();
+        vocabulary.extend(vec![("zero".to_string(), 0)]);
+        let lines = vec!["onezero".to_string()];
+        assert_eq!(day1_part2(&lines, &vocabulary, false).unwrap(), 10);
+    }
+
+    #[test]
+    fn vocabulary_errors() {
+        let err = Vocabulary::from_lines(&["one=1", "two"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: expected '<token>=<value>': \"two\""
+        );
+
+        let err = Vocabulary::from_lines(&["three=x"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 7: expected a number: \"three=x\""
+        );
+
+        let err = Vocabulary::from_lines(&["# big", "big=500000000"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 5: expected a digit 0-9: \"big=500000000\""
+        );
+
+        assert!(Vocabulary::from_lines(&["=3"]).is_err());
+    }
+
+    #[test]
+    fn bidirectional_scan() {
+        let vocabulary = Vocabulary::english();
+        let scanner = vocabulary.scanner();
+        assert_eq!(scanner.calibration("oneight"), Some((1, 8)));
+        assert_eq!(scanner.calibration("xtwone"), Some((2, 1)));
+        assert_eq!(scanner.calibration("eeightt"), Some((8, 8)));
+        assert_eq!(scanner.calibration("0abc5"), Some((0, 5)));
+        assert_eq!(scanner.calibration("abc"), None);
+
+        let matcher = vocabulary.matcher();
+        for line in generate(200, 300, 40) {
+            assert_eq!(
+                scanner.calibration(&line),
+                calibration(&matcher, &line),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn nested_patterns() {
+        let matcher = Matcher::new([("abcd", 1), ("bc", 2), ("c", 3), ("abc", 4)]);
+        let matches: Vec<(usize, usize, u32)> = matcher
+            .matches("xabcd")
+            .map(|m| (m.start, m.end, m.value))
+            .collect();
+        assert_eq!(matches, vec![(1, 4, 4), (2, 4, 2), (3, 4, 3), (1, 5, 1)]);
+    }
 }
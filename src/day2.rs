@@ -1,90 +1,334 @@
-use std::fs::File;
-use std::io;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fmt::Display;
 use std::io::BufRead;
 
-trait AppendWith<T> {
-    fn append(&self, value: T) -> Self;
+use crate::error::{column_at, Error, Result};
+use crate::solution::{read_lines, Answer, Example, Solution};
+
+const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+";
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<Game>;
+
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            part: 1,
+            input: EXAMPLE,
+            answer: 8,
+        },
+        Example {
+            part: 2,
+            input: EXAMPLE,
+            answer: 2286,
+        },
+    ];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input> {
+        parse_games(&read_lines(input)?, &default_bag())
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let sum = day2_part1(input, &default_bag());
+        Answer::try_from(sum).map_err(|_| Error::AnswerOverflow(sum))
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        let sum = day2_part2(input, &default_bag())?;
+        Answer::try_from(sum).map_err(|_| Error::AnswerOverflow(sum))
+    }
 }
 
-impl AppendWith<u32> for u32 {
-    fn append(&self, value: u32) -> Self {
-        self * 10 + value
+// every color of a sample has to be in the bag
+pub fn parse_games<S: AsRef<str>>(lines: &[S], bag: &Sample) -> Result<Vec<Game>> {
+    let mut games: Vec<Game> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        games.push(Game::parse(i + 1, line.as_ref(), bag)?);
     }
+
+    Ok(games)
 }
 
-struct Sample {
-    red: u32,
-    green: u32,
-    blue: u32,
+// the bag of the puzzle
+const BAG: [(&str, u32); 3] = [("red", 12), ("green", 13), ("blue", 14)];
+
+pub fn default_bag() -> Sample {
+    BAG.into_iter()
+        .map(|(color, count)| (color.to_string(), count))
+        .collect()
+}
+
+// cube counts by color, also used for the contents of a bag
+#[derive(Debug, Default, PartialEq)]
+pub struct Sample {
+    cubes: BTreeMap<String, u32>,
 }
 
 impl Sample {
-    fn new() -> Self {
-        Sample {
-            red: 0,
-            green: 0,
-            blue: 0,
-        }
+    fn count(&self, color: &str) -> u32 {
+        self.cubes.get(color).copied().unwrap_or(0)
     }
 
-    fn is_possible(&self, max: &Sample) -> bool {
-        self.red <= max.red && self.green <= max.green && self.blue <= max.blue
+    fn is_possible(&self, bag: &Sample) -> bool {
+        self.cubes
+            .iter()
+            .all(|(color, &count)| count <= bag.count(color))
+    }
+
+    // product of the counts of `colors`, a missing color counts as 0. None if
+    // it does not fit in 64 bits.
+    fn power(&self, colors: &Sample) -> Option<u64> {
+        colors.cubes.keys().try_fold(1_u64, |power, color| {
+            power.checked_mul(u64::from(self.count(color)))
+        })
+    }
+
+    fn total(&self) -> u64 {
+        self.cubes.values().map(|&count| u64::from(count)).sum()
     }
 
-    fn power(&self) -> u32 {
-        self.red * self.green * self.blue
+    // ln of the probability to draw exactly `sample` at once, without
+    // replacement, out of the cubes of `self`
+    fn ln_draw_probability(&self, sample: &Sample) -> f64 {
+        let ways: f64 = sample
+            .cubes
+            .iter()
+            .map(|(color, &count)| ln_binomial(self.count(color).into(), count.into()))
+            .sum();
+        // the sample may not even fit in the bag
+        if ways == f64::NEG_INFINITY {
+            return ways;
+        }
+        ways - ln_binomial(self.total(), sample.total())
     }
 }
 
-struct Game {
-    id: u32,
-    samples: Vec<Sample>,
+impl FromIterator<(String, u32)> for Sample {
+    fn from_iter<I: IntoIterator<Item = (String, u32)>>(iter: I) -> Sample {
+        Sample {
+            cubes: iter.into_iter().collect(),
+        }
+    }
 }
 
-impl Game {
-    fn parse(s: String) -> Result<Game, &'static str> {
-        let mut game_id: u32 = 0;
-        let mut samples: Vec<Sample> = Vec::new();
-
-        let mut current_num: u32 = 0;
-        let mut current_sample: Sample = Sample::new();
-        for c in s.strip_prefix("Game ").unwrap().chars() {
-            match c {
-                digit if c.is_digit(10) => {
-                    current_num = current_num.append(digit.to_digit(10).unwrap());
-                }
-                ':' => {
-                    game_id = current_num;
-                    current_num = 0;
-                }
-                ';' => {
-                    samples.push(current_sample);
-                    current_sample = Sample::new();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(u32),
+    Colon,
+    Comma,
+    Semicolon,
+}
+
+impl Token<'_> {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("'{}'", word),
+            Token::Number(n) => format!("number {}", n),
+            Token::Colon => "':'".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::Semicolon => "';'".to_string(),
+        }
+    }
+}
+
+// tokens of the line together with their byte offsets
+fn tokenize(line_no: usize, line: &str) -> Result<Vec<(usize, Token<'_>)>> {
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let mut take_while = |f: fn(char) -> bool| {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !f(c) {
+                    break;
                 }
-                'r' | 'g' | 'b' => {
-                    if current_num == 0 {
-                        continue;
-                    };
-                    match c {
-                        'r' => current_sample.red = current_num,
-                        'g' => current_sample.green = current_num,
-                        'b' => current_sample.blue = current_num,
-                        _ => {}
-                    }
-                    current_num = 0;
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            &line[start..end]
+        };
+
+        let token = match c {
+            c if c.is_whitespace() => {
+                take_while(char::is_whitespace);
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let digits = take_while(|c| c.is_ascii_digit());
+                let n = digits.parse::<u32>().map_err(|_| {
+                    Error::parse(line_no, column_at(line, start), line, "number too large")
+                })?;
+                Token::Number(n)
+            }
+            c if c.is_alphabetic() => Token::Word(take_while(char::is_alphabetic)),
+            ':' | ',' | ';' => {
+                chars.next();
+                match c {
+                    ':' => Token::Colon,
+                    ',' => Token::Comma,
+                    _ => Token::Semicolon,
                 }
-                _ => {}
             }
+            other => {
+                return Err(Error::parse(
+                    line_no,
+                    column_at(line, start),
+                    line,
+                    format!("unexpected character '{}'", other),
+                ))
+            }
+        };
+
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+// recursive descent over "Game <id>: <count> <color>, ...; ..."
+struct Parser<'a> {
+    line_no: usize,
+    line: &'a str,
+    bag: &'a Sample,
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> Error {
+        let (offset, found) = match self.tokens.get(self.pos) {
+            Some((offset, token)) => (*offset, token.describe()),
+            None => (self.line.len(), "end of line".to_string()),
+        };
+
+        Error::parse(
+            self.line_no,
+            column_at(self.line, offset),
+            self.line,
+            format!("{}, found {}", message, found),
+        )
+    }
+
+    fn next_if(&mut self, expected: Token) -> bool {
+        let found = self.tokens.get(self.pos).map(|(_, token)| *token) == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        if self.next_if(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", expected.describe())))
+        }
+    }
+
+    fn number(&mut self, message: &str) -> Result<u32> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Number(n))) => {
+                self.pos += 1;
+                Ok(*n)
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    fn word(&mut self, message: &str) -> Result<(usize, &'a str)> {
+        match self.tokens.get(self.pos) {
+            Some((offset, Token::Word(word))) => {
+                self.pos += 1;
+                Ok((*offset, word))
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    fn game(&mut self) -> Result<Game> {
+        self.expect(Token::Word("Game"))?;
+        let id = self.number("missing game id")?;
+        self.expect(Token::Colon)?;
+
+        let mut samples: Vec<Sample> = vec![self.sample()?];
+        while self.next_if(Token::Semicolon) {
+            samples.push(self.sample()?);
         }
 
-        samples.push(current_sample);
+        if self.pos < self.tokens.len() {
+            return Err(self.error("expected ',' or ';'"));
+        }
 
         Ok(Game {
-            id: game_id,
+            id,
+            line_no: self.line_no,
+            line: self.line.to_string(),
             samples,
         })
     }
 
+    fn sample(&mut self) -> Result<Sample> {
+        let mut sample = Sample::default();
+
+        loop {
+            let count = self.number("expected a cube count")?;
+            let (offset, color) = self.word("expected a color")?;
+
+            if !self.bag.cubes.contains_key(color) {
+                return Err(Error::parse(
+                    self.line_no,
+                    column_at(self.line, offset),
+                    self.line,
+                    format!("unknown color '{}'", color),
+                ));
+            }
+            if sample.cubes.insert(color.to_string(), count).is_some() {
+                return Err(Error::semantic(
+                    self.line_no,
+                    column_at(self.line, offset),
+                    self.line,
+                    format!("duplicate color '{}' in sample", color),
+                ));
+            }
+
+            if !self.next_if(Token::Comma) {
+                return Ok(sample);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Game {
+    id: u32,
+    line_no: usize,
+    line: String,
+    samples: Vec<Sample>,
+}
+
+impl Game {
+    fn parse(line_no: usize, s: &str, bag: &Sample) -> Result<Game> {
+        let mut parser = Parser {
+            line_no,
+            line: s,
+            bag,
+            tokens: tokenize(line_no, s)?,
+            pos: 0,
+        };
+
+        parser.game()
+    }
+
     fn is_possible(&self, max: &Sample) -> bool {
         for sample in self.samples.iter() {
             if !sample.is_possible(max) {
@@ -95,48 +339,572 @@ impl Game {
         true
     }
 
-    fn min_possible_sample(&self) -> Option<Sample> {
-        let mut min = Sample::new();
+    // the first color of the first sample with more cubes than the bag
+    fn first_violation(&self, bag: &Sample) -> Option<Violation> {
+        self.samples.iter().enumerate().find_map(|(i, sample)| {
+            sample.cubes.iter().find_map(|(color, &count)| {
+                let excess = count.checked_sub(bag.count(color)).filter(|&e| e > 0)?;
+                Some(Violation {
+                    sample: i + 1,
+                    color: color.clone(),
+                    count,
+                    excess,
+                })
+            })
+        })
+    }
+
+    // mean count of every color seen in the game, samples without it count as 0
+    fn mean_sample(&self) -> BTreeMap<String, f64> {
+        let mut sums: BTreeMap<String, u64> = BTreeMap::new();
+        for sample in &self.samples {
+            for (color, &count) in &sample.cubes {
+                *sums.entry(color.clone()).or_insert(0) += u64::from(count);
+            }
+        }
+
+        sums.into_iter()
+            .map(|(color, sum)| (color, sum as f64 / self.samples.len() as f64))
+            .collect()
+    }
+
+    fn min_possible_sample(&self) -> Sample {
+        let mut min = Sample::default();
 
         for next in self.samples.iter() {
-            min.red = u32::max(min.red, next.red);
-            min.green = u32::max(min.green, next.green);
-            min.blue = u32::max(min.blue, next.blue);
+            for (color, &count) in &next.cubes {
+                let min_count = min.cubes.entry(color.clone()).or_insert(0);
+                *min_count = u32::max(*min_count, count);
+            }
         }
 
-        Some(min)
+        min
     }
-}
 
-pub fn day2_part1(file: &mut io::BufReader<File>) -> u32 {
-    let mut res: u32 = 0;
-    let mut possible_games: Vec<u32> = Vec::new();
+    fn power(&self, bag: &Sample) -> Result<u64> {
+        self.min_possible_sample()
+            .power(bag)
+            .ok_or_else(|| Error::semantic(self.line_no, 1, &self.line, "power overflows"))
+    }
+
+    // every sample is drawn from the full bag, the cubes go back in between
+    fn ln_likelihood(&self, bag: &Sample) -> f64 {
+        self.samples
+            .iter()
+            .map(|sample| bag.ln_draw_probability(sample))
+            .sum()
+    }
 
-    for line in file.lines() {
-        let game = Game::parse(line.unwrap()).unwrap();
-        if game.is_possible(&Sample {
-            red: 12,
-            green: 13,
-            blue: 14,
-        }) {
-            res += game.id;
-            possible_games.push(game.id);
+    pub fn likelihood(&self, bag: &Sample) -> f64 {
+        self.ln_likelihood(bag).exp()
+    }
+
+    // searches every bag of the colors seen in the game that holds at least the
+    // minimal bag and at most `max_count` cubes of a color. The bound is needed,
+    // the likelihood can keep growing with the size of the bag. There are
+    // (max_count + 1)^colors bags at most, games that need more than
+    // MAX_SEARCHED_BAGS of them are refused.
+    pub fn max_likelihood_bag(&self, max_count: u32) -> Result<(Sample, f64)> {
+        let min_bag = self.min_possible_sample();
+        let ranges: Vec<(&String, u32, u32)> = min_bag
+            .cubes
+            .iter()
+            .map(|(color, &min)| (color, min, max_count.max(min)))
+            .collect();
+
+        let size = ranges.iter().try_fold(1_u64, |size, &(_, min, max)| {
+            size.checked_mul(u64::from(max - min) + 1)
+        });
+        if size.is_none_or(|size| size > MAX_SEARCHED_BAGS) {
+            return Err(Error::semantic(
+                self.line_no,
+                1,
+                &self.line,
+                format!(
+                    "more than {} bags with {} colors and up to {} cubes of a color",
+                    MAX_SEARCHED_BAGS,
+                    ranges.len(),
+                    max_count
+                ),
+            ));
         }
+
+        let mut counts: Vec<u32> = ranges.iter().map(|&(_, min, _)| min).collect();
+        let mut best: (Vec<u32>, f64) = (counts.clone(), f64::NEG_INFINITY);
+        loop {
+            let bag: Sample = ranges
+                .iter()
+                .zip(&counts)
+                .map(|(&(color, _, _), &count)| (color.clone(), count))
+                .collect();
+            let ln_likelihood = self.ln_likelihood(&bag);
+            if ln_likelihood > best.1 {
+                best = (counts.clone(), ln_likelihood);
+            }
+
+            // next bag, odometer style
+            let Some(i) = (0..counts.len()).find(|&i| counts[i] < ranges[i].2) else {
+                break;
+            };
+            counts[i] += 1;
+            for (j, count) in counts.iter_mut().enumerate().take(i) {
+                *count = ranges[j].1;
+            }
+        }
+
+        let bag = ranges
+            .iter()
+            .zip(&best.0)
+            .map(|(&(color, _, _), &count)| (color.clone(), count))
+            .collect();
+        Ok((bag, best.1.exp()))
     }
+}
 
-    println!("possible games: {:?}", possible_games);
+pub fn possible_games(games: &[Game], bag: &Sample) -> Vec<u32> {
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id)
+        .collect()
+}
 
-    res
+pub fn day2_part1(games: &[Game], bag: &Sample) -> u64 {
+    possible_games(games, bag).into_iter().map(u64::from).sum()
 }
 
-pub fn day2_part2(file: &mut io::BufReader<File>) -> u32 {
-    let mut res: u32 = 0;
+// the power is taken over the colors of the bag
+pub fn day2_part2(games: &[Game], bag: &Sample) -> Result<u64> {
+    let mut res: u64 = 0;
 
-    for line in file.lines() {
-        let game = Game::parse(line.unwrap()).unwrap();
-        let min_sample = game.min_possible_sample().unwrap();
-        res += min_sample.power();
+    for game in games {
+        res = res.checked_add(game.power(bag)?).ok_or_else(|| {
+            Error::semantic(game.line_no, 1, &game.line, "sum of powers overflows")
+        })?;
     }
 
-    res
+    Ok(res)
+}
+
+pub fn print_possible(games: &[Game], bag: &Sample) -> Result<()> {
+    println!("possible games: {:?}", possible_games(games, bag));
+    println!("sum of possible game ids: {}", day2_part1(games, bag));
+    println!("sum of powers: {}", day2_part2(games, bag)?);
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    // 1-based index of the sample in the game
+    pub sample: usize,
+    pub color: String,
+    pub count: u32,
+    pub excess: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GameReport {
+    pub id: u32,
+    // also the maximum draw of every color
+    pub min_bag: Sample,
+    pub power: u64,
+    pub violation: Option<Violation>,
+    pub mean: BTreeMap<String, f64>,
+}
+
+pub fn report(games: &[Game], bag: &Sample) -> Result<Vec<GameReport>> {
+    games
+        .iter()
+        .map(|game| {
+            Ok(GameReport {
+                id: game.id,
+                power: game.power(bag)?,
+                min_bag: game.min_possible_sample(),
+                violation: game.first_violation(bag),
+                mean: game.mean_sample(),
+            })
+        })
+        .collect()
+}
+
+// number of values in each bucket of `width`, empty buckets are left out
+fn histogram(values: &[u32], width: u32) -> Vec<(u32, usize)> {
+    let mut buckets: BTreeMap<u32, usize> = BTreeMap::new();
+    for value in values {
+        *buckets.entry(value / width).or_insert(0) += 1;
+    }
+
+    buckets
+        .into_iter()
+        .map(|(i, count)| (i * width, count))
+        .collect()
+}
+
+fn print_histogram(title: &str, values: &[u32], width: u32) {
+    println!("{}", title);
+    for (start, count) in histogram(values, width) {
+        let line = format!(
+            "  {:>5}..{:<5} {:>4} {}",
+            start,
+            start.saturating_add(width),
+            count,
+            "#".repeat(count)
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+fn format_counts<'a, T: Display>(cubes: impl Iterator<Item = (&'a String, T)>) -> String {
+    cubes
+        .map(|(color, count)| format!("{}={}", color, count))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn print_report(games: &[Game], bag: &Sample) -> Result<()> {
+    let reports = report(games, bag)?;
+
+    for report in &reports {
+        let status = match &report.violation {
+            None => "possible".to_string(),
+            Some(v) => format!(
+                "sample {} has {} {}, {} more than the bag",
+                v.sample, v.count, v.color, v.excess
+            ),
+        };
+        println!(
+            "game {}: min bag {}, power {}, {}",
+            report.id,
+            format_counts(report.min_bag.cubes.iter()),
+            report.power,
+            status
+        );
+
+        let mean = report
+            .mean
+            .iter()
+            .map(|(color, mean)| (color, format!("{:.2}", mean)));
+        println!("  mean {}", format_counts(mean));
+    }
+
+    let possible: Vec<&GameReport> = reports
+        .iter()
+        .filter(|report| report.violation.is_none())
+        .collect();
+    println!();
+    println!(
+        "possible games: {} of {}, sum of ids {}",
+        possible.len(),
+        reports.len(),
+        day2_part1(games, bag)
+    );
+    println!("sum of powers: {}", day2_part2(games, bag)?);
+
+    let mut colors: Vec<&String> = bag.cubes.keys().collect();
+    for report in &reports {
+        colors.extend(report.min_bag.cubes.keys());
+    }
+    colors.sort();
+    colors.dedup();
+
+    for color in colors {
+        let max_draws: Vec<u32> = reports
+            .iter()
+            .map(|report| report.min_bag.count(color))
+            .collect();
+        println!();
+        print_histogram(&format!("max draws of {}", color), &max_draws, 5);
+    }
+
+    let excesses: Vec<u32> = reports
+        .iter()
+        .filter_map(|report| report.violation.as_ref().map(|v| v.excess))
+        .collect();
+    if !excesses.is_empty() {
+        println!();
+        print_histogram("excess of the first violation", &excesses, 5);
+    }
+
+    Ok(())
+}
+
+// bags max_likelihood_bag tries before giving up
+const MAX_SEARCHED_BAGS: u64 = 100_000;
+
+// ln n!, summed up for small n and from Stirling's series above, where the
+// first left out term is below 1e-13
+fn ln_factorial(n: u64) -> f64 {
+    if n < 32 {
+        return (2..=n).map(|i| (i as f64).ln()).sum();
+    }
+    let n = n as f64;
+    n * n.ln() - n + 0.5 * (2.0 * PI * n).ln() + 1.0 / (12.0 * n) - 1.0 / (360.0 * n.powi(3))
+        + 1.0 / (1260.0 * n.powi(5))
+}
+
+// ln C(n, k)
+fn ln_binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+pub fn print_inference(games: &[Game], bag: &Sample, max_count: u32) -> Result<()> {
+    for game in games {
+        let (best, likelihood) = game.max_likelihood_bag(max_count)?;
+        println!(
+            "game {}: most likely bag {} ({:.3e}), given bag {:.3e}",
+            game.id,
+            format_counts(best.cubes.iter()),
+            likelihood,
+            game.likelihood(bag)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::check_examples;
+
+    #[test]
+    fn examples() {
+        check_examples::<Day2>();
+    }
+
+    fn parse_error(line: &str) -> String {
+        match Game::parse(3, line, &default_bag()) {
+            Ok(_) => panic!("{} parsed", line),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let game = Game::parse(1, "Game 12:  3 blue,4 red ;1 green", &default_bag()).unwrap();
+        assert_eq!(game.id, 12);
+        assert_eq!(game.samples.len(), 2);
+        assert_eq!(game.samples[0].count("blue"), 3);
+        assert_eq!(game.samples[0].count("red"), 4);
+        assert_eq!(game.samples[0].count("green"), 0);
+        assert_eq!(game.samples[1].count("green"), 1);
+    }
+
+    #[test]
+    fn other_colors() {
+        let lines = [
+            "Game 1: 3 blue, 2 yellow; 1 red",
+            "Game 2: 4 yellow, 1 red, 2 green, 1 blue",
+        ];
+        let colors = bag(&[("red", 12), ("green", 13), ("blue", 14), ("yellow", 3)]);
+        let games = parse_games(&lines, &colors).unwrap();
+        assert_eq!(day2_part1(&games, &colors), 1);
+
+        // only the second game has every color of the bag, blue * green * red * yellow = 8
+        assert_eq!(day2_part2(&games, &colors).unwrap(), 8);
+
+        let err = parse_games(&lines, &default_bag()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 19: unknown color 'yellow': \"Game 1: 3 blue, 2 yellow; 1 red\""
+        );
+        assert!(parse_games(&["Game 3: 1 purple"], &colors).is_err());
+    }
+
+    #[test]
+    fn power_overflow() {
+        let colors = bag(&[("a", 1), ("b", 1), ("c", 1)]);
+        let lines = ["Game 1: 100000 a, 100000 b, 100000 c"];
+        let games = parse_games(&lines, &colors).unwrap();
+        assert_eq!(day2_part2(&games, &colors).unwrap(), 100_000_u64.pow(3));
+
+        let colors = bag(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        let lines = ["Game 1: 100000 a, 100000 b, 100000 c, 100000 d"];
+        let games = parse_games(&lines, &colors).unwrap();
+        assert_eq!(
+            day2_part2(&games, &colors).unwrap_err().to_string(),
+            format!("line 1, column 1: power overflows: {:?}", lines[0])
+        );
+
+        let lines = [
+            "Game 1: 4000000000 a, 4000000000 b",
+            "Game 2: 4000000000 a, 4000000000 b",
+        ];
+        let colors = bag(&[("a", 1), ("b", 1)]);
+        let games = parse_games(&lines, &colors).unwrap();
+        assert_eq!(
+            day2_part2(&games, &colors).unwrap_err().to_string(),
+            format!("line 2, column 1: sum of powers overflows: {:?}", lines[1])
+        );
+    }
+
+    #[test]
+    fn game_report() {
+        let games = Day2::parse(EXAMPLE.as_bytes()).unwrap();
+        let reports = report(&games, &default_bag()).unwrap();
+
+        assert_eq!(possible_games(&games, &default_bag()), vec![1, 2, 5]);
+        assert_eq!(reports[0].violation, None);
+        assert_eq!(reports[0].power, 48);
+        assert_eq!(reports[0].min_bag.count("blue"), 6);
+        assert_eq!(reports[0].mean["green"], 4.0 / 3.0);
+        assert_eq!(
+            reports[2].violation,
+            Some(Violation {
+                sample: 1,
+                color: "red".to_string(),
+                count: 20,
+                excess: 8
+            })
+        );
+        assert_eq!(
+            reports[3].violation,
+            Some(Violation {
+                sample: 3,
+                color: "blue".to_string(),
+                count: 15,
+                excess: 1
+            })
+        );
+
+        let games = parse_games(
+            &["Game 1: 3000000000 red; 3000000000 red; 4294967295 red"],
+            &default_bag(),
+        )
+        .unwrap();
+        let reports = report(&games, &default_bag()).unwrap();
+        assert_eq!(reports[0].mean["red"], 3431655765.0);
+    }
+
+    fn bag(counts: &[(&str, u32)]) -> Sample {
+        counts
+            .iter()
+            .map(|&(color, count)| (color.to_string(), count))
+            .collect()
+    }
+
+    #[test]
+    fn likelihood() {
+        let game = Game::parse(1, "Game 1: 1 red; 1 blue", &default_bag()).unwrap();
+        assert_eq!(game.likelihood(&bag(&[("red", 1), ("blue", 1)])), 0.25);
+        assert_eq!(game.likelihood(&bag(&[("red", 1)])), 0.0);
+
+        // 2 red out of 3 red and 1 blue is 3 of 6 pairs
+        let game = Game::parse(1, "Game 1: 2 red", &default_bag()).unwrap();
+        let likelihood = game.likelihood(&bag(&[("red", 3), ("blue", 1)]));
+        assert!((likelihood - 0.5).abs() < 1e-12);
+
+        let games = Day2::parse(EXAMPLE.as_bytes()).unwrap();
+        assert!(games[0].likelihood(&default_bag()) > 0.0);
+        assert_eq!(games[2].likelihood(&default_bag()), 0.0);
+    }
+
+    #[test]
+    fn max_likelihood_bag() {
+        let game = Game::parse(1, "Game 1: 3 red, 2 blue", &default_bag()).unwrap();
+        let (best, likelihood) = game.max_likelihood_bag(20).unwrap();
+        assert_eq!(best, bag(&[("red", 3), ("blue", 2)]));
+        assert!((likelihood - 1.0).abs() < 1e-12);
+
+        // larger bags explain two different draws better, up to the bound
+        let game = Game::parse(1, "Game 1: 2 red; 2 blue", &default_bag()).unwrap();
+        let (best, likelihood) = game.max_likelihood_bag(6).unwrap();
+        assert_eq!(best, bag(&[("red", 6), ("blue", 6)]));
+        assert!(likelihood > game.likelihood(&bag(&[("red", 2), ("blue", 2)])));
+
+        let (best, _) = game.max_likelihood_bag(0).unwrap();
+        assert_eq!(best, bag(&[("red", 2), ("blue", 2)]));
+
+        // one cube of each color is drawn for sure out of a bag with one of each
+        let colors = bag(&[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("f", 1)]);
+        let game = Game::parse(1, "Game 1: 1 a, 1 b, 1 c, 1 d", &colors).unwrap();
+        let (best, likelihood) = game.max_likelihood_bag(3).unwrap();
+        assert_eq!(best, bag(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]));
+        assert!((likelihood - 1.0).abs() < 1e-12);
+
+        // 20^6 bags are too many to search
+        let line = "Game 1: 1 a, 1 b, 1 c, 1 d, 1 e, 1 f";
+        let game = Game::parse(1, line, &colors).unwrap();
+        assert_eq!(
+            game.max_likelihood_bag(20).unwrap_err().to_string(),
+            format!(
+                "line 1, column 1: more than 100000 bags with 6 colors and up to 20 cubes of a color: {:?}",
+                line
+            )
+        );
+    }
+
+    #[test]
+    fn binomials() {
+        assert_eq!(ln_binomial(5, 0), 0.0);
+        assert_eq!(ln_binomial(3, 4), f64::NEG_INFINITY);
+        assert!((ln_binomial(6, 2) - 15_f64.ln()).abs() < 1e-12);
+        assert!((ln_binomial(40, 20) - 25.649406793250424).abs() < 1e-12);
+        assert!((ln_binomial(1000, 3) - 18.9285038647141).abs() < 1e-12);
+        assert!((ln_factorial(2_000_000_000) - 40832826046.639854).abs() < 1e-3);
+
+        // the cost doesn't grow with the counts
+        let game = Game::parse(1, "Game 1: 2000000000 red", &default_bag()).unwrap();
+        let (best, likelihood) = game.max_likelihood_bag(20).unwrap();
+        assert_eq!(best, bag(&[("red", 2_000_000_000)]));
+        assert!((likelihood - 1.0).abs() < 1e-12);
+        assert_eq!(game.likelihood(&default_bag()), 0.0);
+    }
+
+    #[test]
+    fn histograms() {
+        assert_eq!(histogram(&[], 5), vec![]);
+        assert_eq!(
+            histogram(&[0, 4, 5, 12, 3], 5),
+            vec![(0, 3), (5, 1), (10, 1)]
+        );
+        assert_eq!(
+            histogram(&[1, u32::MAX, 2], 5),
+            vec![(0, 2), (u32::MAX / 5 * 5, 1)]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_error("Gam 1: 3 blue"),
+            "line 3, column 1: expected 'Game', found 'Gam': \"Gam 1: 3 blue\""
+        );
+        assert_eq!(
+            parse_error("Game : 3 blue"),
+            "line 3, column 6: missing game id, found ':': \"Game : 3 blue\""
+        );
+        assert_eq!(
+            parse_error("Game 1 3 blue"),
+            "line 3, column 8: expected ':', found number 3: \"Game 1 3 blue\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 bleu"),
+            "line 3, column 11: unknown color 'bleu': \"Game 1: 3 bleu\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 red, 2 blue, 1 red"),
+            "line 3, column 26: duplicate color 'red' in sample: \"Game 1: 3 red, 2 blue, 1 red\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 red;"),
+            "line 3, column 15: expected a cube count, found end of line: \"Game 1: 3 red;\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 red 4 blue"),
+            "line 3, column 15: expected ',' or ';', found number 4: \"Game 1: 3 red 4 blue\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 red, 4 # blue"),
+            "line 3, column 18: unexpected character '#': \"Game 1: 3 red, 4 # blue\""
+        );
+        assert_eq!(
+            parse_error("Game 1: 3 red; 4 blue, green"),
+            "line 3, column 24: expected a cube count, found 'green': \"Game 1: 3 red; 4 blue, green\""
+        );
+    }
 }
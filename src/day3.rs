@@ -1,305 +1,178 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io;
+use std::collections::BTreeMap;
 use std::io::BufRead;
-use std::iter::Map;
 
-trait AppendWith<T> {
-    fn append(&self, value: T) -> Self;
-}
-
-impl AppendWith<u32> for u32 {
-    fn append(&self, value: u32) -> Self {
-        self * 10 + value
-    }
-}
-
-pub fn day3_part1(file: &mut io::BufReader<File>) -> Result<u32, &'static str> {
-    let mut sum: u32 = 0;
-
-    let mut lines = file.lines();
-    let mut cur_line: Vec<u8>;
-    let mut prev_line: Option<Vec<u8>> = None;
-    let mut next_line: Option<Vec<u8>>;
-
-    match lines.next() {
-        Some(line) => cur_line = line.unwrap().into_bytes(),
-        None => return Err("empty file"),
-    };
-
-    for line in lines {
-        next_line = Some(line.unwrap().into_bytes());
-
-        sum += get_row_sum(&cur_line, &prev_line, &next_line);
-
-        prev_line = Some(cur_line);
-        cur_line = next_line.unwrap();
-    }
-
-    next_line = None;
-    sum += get_row_sum(&cur_line, &prev_line, &next_line);
-
-    Ok(sum)
-}
-
-fn get_row_sum(cur: &Vec<u8>, prev: &Option<Vec<u8>>, next: &Option<Vec<u8>>) -> u32 {
-    let mut sum: u32 = 0;
-
-    let mut cur_num: u32 = 0;
-    let mut num_start: usize = 0;
-    for i in 0..cur.len() {
-        if cur[i].is_ascii_digit() {
-            if cur_num == 0 {
-                num_start = i;
-            }
-            cur_num = cur_num.append((cur[i] - b'0') as u32);
-            continue;
-        }
-
-        if cur_num == 0 {
-            continue;
-        }
-
-        if is_part_number(num_start, i - 1, cur, prev, next) {
-            sum += cur_num;
-        } else {
-        }
-
-        cur_num = 0;
+use crate::error::{Error, Result};
+use crate::grid::{Grid, Number};
+use crate::solution::{read_lines, Answer, Example, Solution};
+
+const EXAMPLE: &str = "\
+467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..
+";
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Grid<u8>;
+
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            part: 1,
+            input: EXAMPLE,
+            answer: 4361,
+        },
+        Example {
+            part: 2,
+            input: EXAMPLE,
+            answer: 467835,
+        },
+    ];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input> {
+        Grid::parse(&read_lines(input)?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let sum = day3_part1(input)?;
+        Answer::try_from(sum).map_err(|_| Error::AnswerOverflow(sum))
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        let sum = day3_part2(input)?;
+        Answer::try_from(sum).map_err(|_| Error::AnswerOverflow(sum))
     }
-
-    if cur_num != 0 {
-        if is_part_number(num_start, cur.len() - 1, cur, prev, next) {
-            sum += cur_num;
-        }
-    }
-
-    sum
 }
 
-fn is_part_number(
-    start: usize,
-    end: usize,
-    cur: &Vec<u8>,
-    prev: &Option<Vec<u8>>,
-    next: &Option<Vec<u8>>,
-) -> bool {
-    #[rustfmt::skip]
-    let check = |ch: u8| -> bool { 
-        !ch.is_ascii_digit() && ch != b'.' 
-    };
-
-    if let Some(prev) = prev {
-        for i in start..=end {
-            if check(prev[i]) {
-                return true;
-            }
-        }
-
-        if start >= 1 && check(prev[start - 1]) {
-            return true;
-        }
-
-        if end < prev.len() - 1 && check(prev[end + 1]) {
-            return true;
-        }
-    }
-
-    if let Some(next) = next {
-        for i in start..=end {
-            if check(next[i]) {
-                return true;
-            }
-        }
-
-        if start >= 1 && check(next[start - 1]) {
-            return true;
-        }
-
-        if end < next.len() - 1 && check(next[end + 1]) {
-            return true;
+pub fn day3_part1(grid: &Grid<u8>) -> Result<u64> {
+    let mut sum: u64 = 0;
+    for number in grid.numbers()? {
+        if is_part_number(grid, &number) {
+            sum = sum
+                .checked_add(number.value)
+                .ok_or_else(|| grid.error(number.row, number.col_start, "sum overflows"))?;
         }
     }
 
-    if start >= 1 && check(cur[start - 1]) {
-        return true;
-    }
-
-    if end < cur.len() - 1 && check(cur[end + 1]) {
-        return true;
-    }
-
-    false
-}
-
-struct Line {
-    index: usize,
-    content: Vec<u8>,
+    Ok(sum)
 }
 
 type Position = (usize, usize);
 
-struct AdjacentNumber {
-    gear_position: Position,
-    num: u32,
+// positions around the number, without duplicates
+fn adjacent_cells(grid: &Grid<u8>, number: &Number) -> Vec<Position> {
+    let columns = number.col_start..number.col_end;
+    let mut cells: Vec<Position> = columns
+        .clone()
+        .flat_map(|col| grid.neighbors8(number.row, col))
+        .filter(|&(r, c)| r != number.row || !columns.contains(&c))
+        .collect();
+    cells.sort();
+    cells.dedup();
+
+    cells
 }
 
-pub fn day3_part2(file: &mut io::BufReader<File>) -> Result<u32, &'static str> {
-    let mut lines = file.lines();
-    let mut cur_line: Line;
-    let mut prev_line: Option<Line> = None;
-    let mut next_line: Option<Line>;
-
-    let mut adjacent_nums: HashMap<Position, Vec<AdjacentNumber>> = HashMap::new();
-    match lines.next() {
-        Some(line) => {
-            cur_line = Line {
-                index: 0,
-                content: line.unwrap().into_bytes(),
-            }
-        }
-        None => return Err("empty file"),
-    };
-
-    for (i, line) in lines.enumerate() {
-        next_line = Some(Line {
-            content: line.unwrap().into_bytes(),
-            index: i,
-        });
-
-        let row_adjacent_nums = get_adjacent_nums(&cur_line, &prev_line, &next_line);
-        for num in row_adjacent_nums {
-            if let Some(nums) = adjacent_nums.get_mut(&num.gear_position) {
-                nums.push(num)
-            } else {
-                adjacent_nums.insert(num.gear_position, vec![num]);
-            }
-        }
+fn is_part_number(grid: &Grid<u8>, number: &Number) -> bool {
+    adjacent_cells(grid, number)
+        .into_iter()
+        .filter_map(|(r, c)| grid.get(r, c))
+        .any(|&ch| !ch.is_ascii_digit() && ch != b'.')
+}
 
-        prev_line = Some(cur_line);
-        cur_line = next_line.unwrap();
-    }
+pub fn day3_part2(grid: &Grid<u8>) -> Result<u64> {
+    let mut adjacent_nums: BTreeMap<Position, Vec<u64>> = BTreeMap::new();
 
-    next_line = None;
-    let row_adjacent_nums = get_adjacent_nums(&cur_line, &prev_line, &next_line);
-    for num in row_adjacent_nums {
-        if let Some(nums) = adjacent_nums.get_mut(&num.gear_position) {
-            nums.push(num)
-        } else {
-            adjacent_nums.insert(num.gear_position, vec![num]);
+    for number in grid.numbers()? {
+        for gear_position in get_adjacent_gear_positions(grid, &number) {
+            adjacent_nums
+                .entry(gear_position)
+                .or_default()
+                .push(number.value);
         }
     }
 
-    let mut sum: u32 = 0;
-    for (_, nums) in adjacent_nums {
+    let mut sum: u64 = 0;
+    for ((row, col), nums) in adjacent_nums {
         if nums.len() == 2 {
-            sum += nums.get(0).unwrap().num * nums.get(1).unwrap().num;
+            sum = nums[0]
+                .checked_mul(nums[1])
+                .and_then(|ratio| sum.checked_add(ratio))
+                .ok_or_else(|| grid.error(row, col, "gear ratio sum overflows"))?;
         }
     }
 
     Ok(sum)
 }
 
-fn get_adjacent_nums(
-    cur: &Line,
-    prev: &Option<Line>,
-    next: &Option<Line>,
-) -> Vec<(AdjacentNumber)> {
-    let mut res: Vec<AdjacentNumber> = Vec::new();
-
-    let mut cur_num: u32 = 0;
-    let mut num_start: usize = 0;
-    for i in 0..cur.content.len() {
-        if cur.content[i].is_ascii_digit() {
-            if cur_num == 0 {
-                num_start = i;
-            }
-            cur_num = cur_num.append((cur.content[i] - b'0') as u32);
-            continue;
-        }
-
-        if cur_num == 0 {
-            continue;
-        }
+fn get_adjacent_gear_positions(grid: &Grid<u8>, number: &Number) -> Vec<Position> {
+    adjacent_cells(grid, number)
+        .into_iter()
+        .filter(|&(r, c)| grid.get(r, c) == Some(&b'*'))
+        .collect()
+}
 
-        get_adjacent_gear_positions(num_start, i - 1, cur, prev, next)
-            .iter()
-            .cloned()
-            .for_each(|x| {
-                res.push(AdjacentNumber {
-                    gear_position: x,
-                    num: cur_num,
-                });
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::check_examples;
 
-        cur_num = 0;
+    #[test]
+    fn examples() {
+        check_examples::<Day3>();
     }
 
-    if cur_num != 0 {
-        get_adjacent_gear_positions(num_start, cur.content.len() - 1, cur, prev, next)
-            .iter()
-            .cloned()
-            .for_each(|x| {
-                res.push(AdjacentNumber {
-                    gear_position: x,
-                    num: cur_num,
-                });
-            });
+    #[test]
+    fn gears_on_first_rows() {
+        let grid = Grid::parse(&["1*..", ".*2."]).unwrap();
+        assert_eq!(day3_part2(&grid).unwrap(), 4);
     }
 
-    res
-}
-
-fn get_adjacent_gear_positions(
-    start: usize,
-    end: usize,
-    cur: &Line,
-    prev: &Option<Line>,
-    next: &Option<Line>,
-) -> Vec<Position> {
-    let mut res: Vec<Position> = Vec::new();
-
-    let check = |ch: u8| -> bool { ch == b'*' };
-
-    if let Some(prev) = prev {
-        for i in start..=end {
-            if check(prev.content[i]) {
-                res.push((prev.index, i))
-            }
-        }
-
-        if start >= 1 && check(prev.content[start - 1]) {
-            res.push((prev.index, start - 1))
-        }
+    #[test]
+    fn zeros() {
+        let grid = Grid::parse(&["007*0...", "........", "0.#.05*0"]).unwrap();
+        // 007, 0, 05 and 0 are part numbers, both gears have a 0
+        assert_eq!(day3_part1(&grid).unwrap(), 12);
+        assert_eq!(day3_part2(&grid).unwrap(), 0);
 
-        if end < prev.content.len() - 1 && check(prev.content[end + 1]) {
-            res.push((prev.index, end + 1))
-        }
+        let grid = Grid::parse(&["10*007"]).unwrap();
+        assert_eq!(day3_part2(&grid).unwrap(), 70);
     }
 
-    if let Some(next) = next {
-        for i in start..=end {
-            if check(next.content[i]) {
-                res.push((next.index, i))
-            }
-        }
+    #[test]
+    fn overflow() {
+        let grid = Grid::parse(&["99999999999*99999999999"]).unwrap();
+        assert_eq!(
+            day3_part2(&grid).unwrap_err().to_string(),
+            "line 1, column 12: gear ratio sum overflows: \"99999999999*99999999999\""
+        );
 
-        if start >= 1 && check(next.content[start - 1]) {
-            res.push((next.index, start - 1))
-        }
+        let grid = Grid::parse(&["18446744073709551615#1"]).unwrap();
+        assert_eq!(
+            day3_part1(&grid).unwrap_err().to_string(),
+            "line 1, column 22: sum overflows: \"18446744073709551615#1\""
+        );
 
-        if end < next.content.len() - 1 && check(next.content[end + 1]) {
-            res.push((next.index, end + 1))
-        }
-    }
-
-    if start >= 1 && check(cur.content[start - 1]) {
-        res.push((cur.index, start - 1))
+        let grid = Grid::parse(&["9999999999999999999#"]).unwrap();
+        assert_eq!(
+            Day3::part1(&grid).unwrap_err().to_string(),
+            "answer 9999999999999999999 does not fit in i64"
+        );
     }
 
-    if end < cur.content.len() - 1 && check(cur.content[end + 1]) {
-        res.push((cur.index, end + 1))
+    #[test]
+    fn ragged_input() {
+        let err = Day3::parse("467..\n...*\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 5: row has 4 columns, expected 5: \"...*\""
+        );
     }
-
-    res
 }
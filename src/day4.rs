@@ -1,61 +1,126 @@
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io;
 use std::io::BufRead;
 use std::str::FromStr;
 
-pub fn day4_part1(file: &mut io::BufReader<File>) -> Result<u32, Box<dyn std::error::Error>> {
-    let re = Regex::new(r"Card +[0-9]+:(?<winning>[ 0-9]+)\|(?<got>[ 0-9]+)")?;
+use crate::error::{column_at, Error, Result};
+use crate::solution::{read_lines, Answer, Example, Solution};
+
+const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<String>;
+
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            part: 1,
+            input: EXAMPLE,
+            answer: 13,
+        },
+        Example {
+            part: 2,
+            input: EXAMPLE,
+            answer: 30,
+        },
+    ];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input> {
+        read_lines(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(day4_part1(input)?.into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(day4_part2(input)?.into())
+    }
+}
+
+pub fn day4_part1(lines: &[String]) -> Result<u32> {
+    let re = Regex::new(r"Card +[0-9]+:(?<winning>[ 0-9]+)\|(?<got>[ 0-9]+)").expect("valid regex");
     let mut sum: u32 = 0;
 
-    for line in file.lines() {
-        for (_, [winning, got]) in re.captures_iter(line?.as_str()).map(|c| c.extract()) {
-            let winning: HashSet<u32> = winning
-                .split_whitespace()
-                .filter_map(|s| u32::from_str(s).ok())
-                .collect();
-            let got: HashSet<u32> = got
-                .split_whitespace()
-                .filter_map(|s| u32::from_str(s).ok())
-                .collect();
-
-            let matches = winning.intersection(&got).count() as u32;
-
-            if matches > 0 {
-                sum += 2_u32.pow(matches - 1);
-            }
+    for (i, line) in lines.iter().enumerate() {
+        let captures = re.captures(line).ok_or_else(|| bad_card(i + 1, line))?;
+        let (_, [winning, got]) = captures.extract();
+        let winning: HashSet<u32> = winning
+            .split_whitespace()
+            .filter_map(|s| u32::from_str(s).ok())
+            .collect();
+        let got: HashSet<u32> = got
+            .split_whitespace()
+            .filter_map(|s| u32::from_str(s).ok())
+            .collect();
+
+        let matches = winning.intersection(&got).count() as u32;
+
+        if matches > 0 {
+            sum += 2_u32.pow(matches - 1);
         }
     }
 
     Ok(sum)
 }
 
-pub fn day4_part2(file: &mut io::BufReader<File>) -> Result<u32, Box<dyn std::error::Error>> {
-    let re = Regex::new(r"Card +(?<index>[0-9]+):(?<winning>[ 0-9]+)\|(?<got>[ 0-9]+)")?;
+pub fn day4_part2(lines: &[String]) -> Result<u32> {
+    let re = Regex::new(r"Card +(?<index>[0-9]+):(?<winning>[ 0-9]+)\|(?<got>[ 0-9]+)")
+        .expect("valid regex");
     let mut bonus_cards: HashMap<u32, u32> = HashMap::new();
     let mut cards_amount = 0;
 
-    for line in file.lines() {
-        for (_, [index, winning, got]) in re.captures_iter(line?.as_str()).map(|c| c.extract()) {
-            let card_index = u32::from_str(index)?;
-            let winning: HashSet<u32> = winning
-                .split_whitespace()
-                .filter_map(|s| u32::from_str(s).ok())
-                .collect();
-            let got: HashSet<u32> = got
-                .split_whitespace()
-                .filter_map(|s| u32::from_str(s).ok())
-                .collect();
-
-            let matches = winning.intersection(&got).count() as u32;
-            let bonus = bonus_cards.get(&card_index).cloned().unwrap_or(1);
-            for i in card_index + 1..=card_index + matches {
-                *bonus_cards.entry(i).or_insert(1) += bonus;
-            }
+    for (i, line) in lines.iter().enumerate() {
+        let captures = re.captures(line).ok_or_else(|| bad_card(i + 1, line))?;
+        let index_column = column_at(line, captures.name("index").map_or(0, |m| m.start()));
+        let (_, [index, winning, got]) = captures.extract();
+
+        let card_index = u32::from_str(index)
+            .map_err(|_| Error::parse(i + 1, index_column, index, "bad card number"))?;
+        let winning: HashSet<u32> = winning
+            .split_whitespace()
+            .filter_map(|s| u32::from_str(s).ok())
+            .collect();
+        let got: HashSet<u32> = got
+            .split_whitespace()
+            .filter_map(|s| u32::from_str(s).ok())
+            .collect();
+
+        let matches = winning.intersection(&got).count() as u32;
+        let bonus = bonus_cards.get(&card_index).cloned().unwrap_or(1);
+        for i in card_index + 1..=card_index + matches {
+            *bonus_cards.entry(i).or_insert(1) += bonus;
         }
         cards_amount += 1
     }
 
     Ok(bonus_cards.values().fold(0, |acc, e| acc + e - 1) + cards_amount)
 }
+
+fn bad_card(line_no: usize, line: &str) -> Error {
+    Error::parse(
+        line_no,
+        1,
+        line,
+        "expected 'Card <id>: <numbers> | <numbers>'",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::check_examples;
+
+    #[test]
+    fn examples() {
+        check_examples::<Day4>();
+    }
+}
@@ -1,399 +1,1206 @@
-use std::cmp::{max, min};
-use std::error::Error;
-use std::fs::File;
-use std::io;
+use std::cmp::min;
 use std::io::BufRead;
 use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{column_at, Error, Result};
+use crate::interval::{IntervalSet, RangeExt};
+use crate::solution::{read_lines, Answer, Example, Solution};
+
+const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Almanac;
+
+    const EXAMPLES: &'static [Example] = &[
+        Example {
+            part: 1,
+            input: EXAMPLE,
+            answer: 35,
+        },
+        Example {
+            part: 2,
+            input: EXAMPLE,
+            answer: 46,
+        },
+    ];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input> {
+        Almanac::from_lines(&read_lines(input)?)
+    }
 
-#[derive(Debug)]
-struct Map {
-    name: String,
-    source_range: Vec<Range<i64>>,
-    offset: Vec<i64>,
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        solve_part1(input)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        solve_part2(input)
+    }
 }
 
-pub fn solve_part1(file: &mut io::BufReader<File>) -> Result<i64, Box<dyn Error>> {
-    let mut lines = file.lines();
+// source range and the offset added to it
+type Entry = (Range<i64>, i64);
 
-    let seeds: Vec<i64> = lines
-        .next()
-        .ok_or("empty file")??
-        .strip_prefix("seeds: ")
-        .ok_or("bad seeds line")?
-        .split_whitespace()
-        .filter_map(|s| s.parse::<i64>().ok())
-        .collect();
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    range: Range<i64>,
+    offset: i64,
+}
 
-    if seeds.len() % 2 != 0 {
-        return Err("odd number of seeds")?;
+// piecewise linear function, the pieces are sorted, non-overlapping and never
+// have a zero offset, every value outside of them maps to itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    source: String,
+    destination: String,
+    pieces: Vec<Piece>,
+}
+
+impl Map {
+    pub fn identity(category: &str) -> Map {
+        Map {
+            source: category.to_string(),
+            destination: category.to_string(),
+            pieces: Vec::new(),
+        }
     }
 
-    let mut maps: Vec<Map> = Vec::with_capacity(7);
-    let mut current_map: Option<Map> = None;
-    for line in lines {
-        let line = line?;
+    // an earlier entry wins if the source ranges overlap
+    pub fn new(source: &str, destination: &str, entries: &[Entry]) -> Map {
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        for (range, offset) in entries.iter().filter(|(range, _)| !range.is_empty()) {
+            let mut uncovered = vec![range.clone()];
+            for piece in &pieces {
+                uncovered = uncovered
+                    .into_iter()
+                    .flat_map(|r| r.cutouts(&piece.range))
+                    .collect();
+            }
 
-        if line.len() == 0 {
-            continue;
+            pieces.extend(uncovered.into_iter().map(|range| Piece {
+                range,
+                offset: *offset,
+            }));
         }
 
-        if line.ends_with(':') {
-            if current_map.is_some() {
-                maps.push(current_map.unwrap());
+        Map::normalized(source, destination, pieces)
+    }
+
+    fn normalized(source: &str, destination: &str, mut pieces: Vec<Piece>) -> Map {
+        pieces.retain(|piece| piece.offset != 0 && !piece.range.is_empty());
+        pieces.sort_by_key(|piece| piece.range.start);
+
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last)
+                    if last.range.end == piece.range.start && last.offset == piece.offset =>
+                {
+                    last.range.end = piece.range.end;
+                }
+                _ => merged.push(piece),
             }
+        }
 
-            current_map = Some(Map {
-                name: line
-                    .strip_suffix(" map:")
-                    .ok_or("bad map name")?
-                    .to_string(),
-                source_range: Vec::new(),
-                offset: Vec::new(),
-            });
-            continue;
+        Map {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            pieces: merged,
         }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}-to-{}", self.source, self.destination)
+    }
+
+    pub fn apply(&self, x: i64) -> i64 {
+        let i = self.pieces.partition_point(|piece| piece.range.start <= x);
+        match i.checked_sub(1).map(|i| &self.pieces[i]) {
+            Some(piece) if piece.range.contains(&x) => x + piece.offset,
+            _ => x,
+        }
+    }
+
+    pub fn apply_range(&self, range: Range<i64>) -> Vec<Range<i64>> {
+        self.segments(range)
+            .into_iter()
+            .map(|(range, offset)| range.add(offset))
+            .collect()
+    }
 
-        let nums: Vec<i64> = line
-            .split_whitespace()
-            .filter_map(|s| s.parse::<i64>().ok())
+    // `other` applied after `self`
+    pub fn compose(&self, other: &Map) -> Map {
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        for (segment, offset) in self.segments(i64::MIN..i64::MAX) {
+            for (image, other_offset) in other.segments(segment.add(offset)) {
+                pieces.push(Piece {
+                    range: image.add(-offset),
+                    offset: offset + other_offset,
+                });
+            }
+        }
+
+        Map::normalized(&self.source, &other.destination, pieces)
+    }
+
+    // the inverse function, if every value has exactly one preimage
+    pub fn invert(&self) -> Option<Map> {
+        let mut images: Vec<(Range<i64>, i64)> = self
+            .segments(i64::MIN..i64::MAX)
+            .into_iter()
+            .map(|(range, offset)| (range.add(offset), offset))
             .collect();
+        images.sort_by_key(|(range, _)| range.start);
 
-        if nums.len() != 3 {
-            return Err("bad nums line")?;
+        // the images of a bijection partition the whole line again
+        let mut end = i64::MIN;
+        for (image, _) in &images {
+            if image.start != end {
+                return None;
+            }
+            end = image.end;
+        }
+        if end != i64::MAX {
+            return None;
+        }
+
+        let pieces = images
+            .into_iter()
+            .map(|(range, offset)| Piece {
+                range,
+                offset: -offset,
+            })
+            .collect();
+
+        Some(Map::normalized(&self.destination, &self.source, pieces))
+    }
+
+    // every source range that is mapped into `range`
+    pub fn preimage(&self, set: &IntervalSet<i64>) -> IntervalSet<i64> {
+        let mut res: IntervalSet<i64> = IntervalSet::new();
+
+        for (segment, offset) in self.segments(i64::MIN..i64::MAX) {
+            let image = IntervalSet::from(segment.add(offset));
+            res = res.union(&set.intersection(&image).shift(-offset));
         }
 
-        let current_map = current_map.as_mut().ok_or("missing map entry")?;
-        current_map.source_range.push(nums[1]..nums[1] + nums[2]);
-        current_map.offset.push(nums[0] - nums[1]);
+        res
     }
 
-    if current_map.is_some() {
-        maps.push(current_map.unwrap());
+    // splits `range` into sorted parts together with the offset applied to each of them
+    fn segments(&self, range: Range<i64>) -> Vec<(Range<i64>, i64)> {
+        let mut res: Vec<(Range<i64>, i64)> = Vec::new();
+        let mut rest: Vec<Range<i64>> = vec![range.clone()];
+
+        let first = self
+            .pieces
+            .partition_point(|piece| piece.range.end <= range.start);
+        for piece in self.pieces[first..]
+            .iter()
+            .take_while(|piece| piece.range.start < range.end)
+        {
+            if let Some(inter) = range.intersection(&piece.range) {
+                rest = rest.into_iter().flat_map(|r| r.cutouts(&inter)).collect();
+                res.push((inter, piece.offset));
+            }
+        }
+
+        res.extend(rest.into_iter().map(|r| (r, 0)));
+        res.sort_by_key(|(range, _)| range.start);
+        res
     }
+}
 
-    // get seeds
-    let mut min_location = i64::MAX;
-    for seed in seeds {
-        let mut source = seed;
-        '_maps: for map in &maps {
-            for (i, range) in map.source_range.iter().enumerate() {
-                if range.contains(&source) {
-                    source = source + map.offset[i];
-                    continue '_maps;
+// a map of the almanac together with the line of its header
+#[derive(Debug)]
+struct Section {
+    line: usize,
+    map: Map,
+}
+
+impl Section {
+    fn describe(&self) -> String {
+        format!("{} (line {})", self.map.name(), self.line)
+    }
+}
+
+#[derive(Debug)]
+pub struct Almanac {
+    seeds: Vec<i64>,
+    seeds_line: String,
+    sections: Vec<Section>,
+}
+
+impl Almanac {
+    // sections along the only conversion path from `source` to `destination`
+    fn route(&self, source: &str, destination: &str) -> Result<Vec<&Section>> {
+        let route_error = |message: String| Error::Route {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            message,
+        };
+
+        let mut paths: Vec<Vec<usize>> = Vec::new();
+        let mut visited: Vec<&str> = vec![source];
+        self.find_paths(
+            source,
+            destination,
+            &mut visited,
+            &mut Vec::new(),
+            &mut paths,
+        );
+
+        match paths.len() {
+            0 if !self.sections.iter().any(|s| s.map.source == source) => {
+                Err(route_error(format!("no map converts from {}", source)))
+            }
+            0 if !self
+                .sections
+                .iter()
+                .any(|s| s.map.destination == destination) =>
+            {
+                Err(route_error(format!("no map converts to {}", destination)))
+            }
+            0 => {
+                let reachable = self.reachable(source);
+                Err(route_error(format!(
+                    "maps from {} only reach {}",
+                    source,
+                    reachable.join(", ")
+                )))
+            }
+            1 => Ok(paths[0].iter().map(|&i| &self.sections[i]).collect()),
+            _ => {
+                let describe = |path: &Vec<usize>| -> String {
+                    let sections: Vec<String> =
+                        path.iter().map(|&i| self.sections[i].describe()).collect();
+                    sections.join(" -> ")
+                };
+                Err(route_error(format!(
+                    "ambiguous, either {} or {}",
+                    describe(&paths[0]),
+                    describe(&paths[1])
+                )))
+            }
+        }
+    }
+
+    // depth-first search for simple paths, stops after two of them are found
+    fn find_paths<'a>(
+        &'a self,
+        from: &str,
+        destination: &str,
+        visited: &mut Vec<&'a str>,
+        path: &mut Vec<usize>,
+        paths: &mut Vec<Vec<usize>>,
+    ) {
+        if from == destination {
+            paths.push(path.clone());
+            return;
+        }
+
+        for (i, section) in self.sections.iter().enumerate() {
+            if paths.len() > 1 {
+                return;
+            }
+            if section.map.source != from || visited.contains(&section.map.destination.as_str()) {
+                continue;
+            }
+
+            visited.push(&section.map.destination);
+            path.push(i);
+            self.find_paths(&section.map.destination, destination, visited, path, paths);
+            path.pop();
+            visited.pop();
+        }
+    }
+
+    fn reachable(&self, source: &str) -> Vec<&str> {
+        let mut reachable: Vec<&str> = Vec::new();
+        let mut queue: Vec<&str> = vec![source];
+
+        while let Some(category) = queue.pop() {
+            for section in self.sections.iter().filter(|s| s.map.source == category) {
+                let next = section.map.destination.as_str();
+                if next != source && !reachable.contains(&next) {
+                    reachable.push(next);
+                    queue.push(next);
                 }
             }
         }
-        min_location = min(min_location, source);
+
+        reachable
     }
 
-    Ok(min_location)
+    // maps along the route composed into a single map
+    pub fn conversion(&self, source: &str, destination: &str) -> Result<Map> {
+        Ok(self
+            .route(source, destination)?
+            .iter()
+            .fold(Map::identity(source), |chain, section| {
+                chain.compose(&section.map)
+            }))
+    }
+
+    // walks the maps backwards and returns every seed range ending up in `locations`
+    pub fn seeds_for(&self, locations: Range<i64>) -> Result<IntervalSet<i64>> {
+        let route = self.route("seed", "location")?;
+
+        Ok(route
+            .iter()
+            .rev()
+            .fold(IntervalSet::from(locations), |set, section| {
+                section.map.preimage(&set)
+            }))
+    }
+
+    // the seed ranges of part 2, every pair of seeds is a start and a length
+    pub fn seed_set(&self) -> Result<IntervalSet<i64>> {
+        let error = |message: String| Error::semantic(1, 1, &self.seeds_line, message);
+
+        self.seeds
+            .chunks(2)
+            .map(|pair| {
+                let (start, length) = (pair[0], pair[1]);
+                if start < 0 {
+                    return Err(error(format!("negative seed range start {}", start)));
+                }
+                if length < 0 {
+                    return Err(error(format!("negative seed range length {}", length)));
+                }
+                match start.checked_add(length) {
+                    Some(end) => Ok(start..end),
+                    None => Err(error(format!(
+                        "seed range {} + {} overflows",
+                        start, length
+                    ))),
+                }
+            })
+            .collect()
+    }
+
+    pub fn chain(&self) -> Result<Map> {
+        self.conversion("seed", "location")
+    }
+
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Result<Almanac> {
+        let (seeds, blocks) = parse_blocks(lines)?;
+
+        let mut sections: Vec<Section> = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let entries = block
+                .rows
+                .iter()
+                .map(|row| row.entry())
+                .collect::<Result<Vec<Entry>>>()?;
+            let map = Map::new(&block.source, &block.destination, &entries);
+            sections.push(Section {
+                line: block.line,
+                map,
+            });
+        }
+
+        Ok(Almanac {
+            seeds,
+            seeds_line: lines[0].as_ref().to_string(),
+            sections,
+        })
+    }
 }
 
-// bruteforce approach
-// TODO: maybe add multithreading if I wouldn't be able to fix overlapping ranges
-pub fn solve_part2(file: &mut io::BufReader<File>) -> Result<i64, Box<dyn Error>> {
-    let mut lines = file.lines();
-
-    let seeds_ranges: Vec<i64> = lines
-        .next()
-        .ok_or("empty file")??
-        .strip_prefix("seeds: ")
-        .ok_or("bad seeds line")?
-        .split_whitespace()
-        .filter_map(|s| s.parse::<i64>().ok())
-        .collect();
+// a "<destination> <source> <length>" line of a map block
+#[derive(Debug)]
+struct Row {
+    line: usize,
+    text: String,
+    nums: [i64; 3],
+}
+
+impl Row {
+    // negative numbers are refused like `check` does, they would let the
+    // offsets of composed maps overflow
+    fn entry(&self) -> Result<Entry> {
+        let [destination, source, length] = self.nums;
+        let error = |message: String| Error::semantic(self.line, 1, &self.text, message);
+
+        if length < 0 {
+            return Err(error(format!("negative length {}", length)));
+        }
+        if source < 0 || destination < 0 {
+            return Err(error(format!(
+                "negative start of {} {}",
+                if source < 0 { "source" } else { "destination" },
+                min(source, destination)
+            )));
+        }
 
-    if seeds_ranges.len() % 2 != 0 {
-        return Err("odd number of seeds")?;
+        match (
+            source.checked_add(length),
+            destination.checked_add(length),
+            destination.checked_sub(source),
+        ) {
+            (Some(end), Some(_), Some(offset)) => Ok((source..end, offset)),
+            _ => Err(error("range overflows".to_string())),
+        }
     }
+}
+
+// a map block as it is written in the almanac
+#[derive(Debug)]
+struct Block {
+    line: usize,
+    source: String,
+    destination: String,
+    rows: Vec<Row>,
+}
+
+fn parse_blocks<S: AsRef<str>>(lines: &[S]) -> Result<(Vec<i64>, Vec<Block>)> {
+    let first_line = lines
+        .first()
+        .map(|line| line.as_ref())
+        .ok_or_else(|| Error::parse(1, 1, "", "empty input"))?;
 
-    let mut seeds: Vec<i64> = Vec::new();
-    for seed in seeds_ranges.chunks(2) {
-        let mut range_seeds: Vec<i64> = (seed[0]..seed[0] + seed[1]).into_iter().collect();
-        seeds.append(&mut range_seeds);
+    const SEEDS_HEADER: &str = "seeds:";
+    if !first_line.starts_with(SEEDS_HEADER) {
+        return Err(Error::parse(1, 1, first_line, "expected 'seeds:'"));
     }
 
-    let mut maps: Vec<Map> = Vec::with_capacity(7);
-    let mut current_map: Option<Map> = None;
-    for line in lines {
-        let line = line?;
+    let seeds: Vec<i64> = parse_numbers(1, first_line, SEEDS_HEADER.len())?;
+    if !seeds.len().is_multiple_of(2) {
+        return Err(Error::semantic(1, 1, first_line, "odd number of seeds"));
+    }
 
-        if line.len() == 0 {
+    let mut blocks: Vec<Block> = Vec::with_capacity(7);
+    for (i, line) in lines.iter().enumerate().skip(1) {
+        let line = line.as_ref();
+        if line.trim().is_empty() {
             continue;
         }
 
         if line.ends_with(':') {
-            if current_map.is_some() {
-                maps.push(current_map.unwrap());
-            }
-
-            current_map = Some(Map {
-                name: line
-                    .strip_suffix(" map:")
-                    .ok_or("bad map name")?
-                    .to_string(),
-                source_range: Vec::new(),
-                offset: Vec::new(),
+            let (source, destination) = line
+                .strip_suffix(" map:")
+                .and_then(|name| name.split_once("-to-"))
+                .ok_or_else(|| {
+                    Error::parse(i + 1, 1, line, "expected '<source>-to-<destination> map:'")
+                })?;
+            blocks.push(Block {
+                line: i + 1,
+                source: source.to_string(),
+                destination: destination.to_string(),
+                rows: Vec::new(),
             });
             continue;
         }
 
-        let nums: Vec<i64> = line
-            .split_whitespace()
-            .filter_map(|s| s.parse::<i64>().ok())
-            .collect();
+        let block = blocks
+            .last_mut()
+            .ok_or_else(|| Error::parse(i + 1, 1, line, "missing map header"))?;
 
+        let nums = parse_numbers(i + 1, line, 0)?;
         if nums.len() != 3 {
-            return Err("bad nums line")?;
+            return Err(Error::parse(
+                i + 1,
+                1,
+                line,
+                format!("expected 3 numbers, found {}", nums.len()),
+            ));
         }
 
-        let current_map = current_map.as_mut().ok_or("missing map entry")?;
-        current_map.source_range.push(nums[1]..nums[1] + nums[2]);
-        current_map.offset.push(nums[0] - nums[1]);
+        block.rows.push(Row {
+            line: i + 1,
+            text: line.to_string(),
+            nums: [nums[0], nums[1], nums[2]],
+        });
     }
 
-    if current_map.is_some() {
-        maps.push(current_map.unwrap());
-    }
+    Ok((seeds, blocks))
+}
 
-    // get seeds
-    let mut min_location = i64::MAX;
-    for seed in seeds {
-        let mut source = seed;
-        '_maps: for map in &maps {
-            for (i, range) in map.source_range.iter().enumerate() {
-                if range.contains(&source) {
-                    source = source + map.offset[i];
-                    continue '_maps;
+#[derive(Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Note,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+// validates the map blocks without building the maps
+pub fn check<S: AsRef<str>>(lines: &[S]) -> Result<Vec<Issue>> {
+    let (_, blocks) = parse_blocks(lines)?;
+    let mut issues: Vec<Issue> = Vec::new();
+
+    for block in &blocks {
+        let mut sources: Vec<(usize, Range<i64>)> = Vec::new();
+        let mut destinations: Vec<(usize, Range<i64>)> = Vec::new();
+
+        for row in &block.rows {
+            let [destination, source, length] = row.nums;
+            let mut error = |message: String| {
+                issues.push(Issue {
+                    line: row.line,
+                    severity: Severity::Error,
+                    message,
+                })
+            };
+
+            if length < 0 {
+                error(format!("negative length {}", length));
+                continue;
+            }
+            if source < 0 || destination < 0 {
+                error(format!(
+                    "negative start of {} {}",
+                    if source < 0 { "source" } else { "destination" },
+                    min(source, destination)
+                ));
+            }
+
+            match (source.checked_add(length), destination.checked_add(length)) {
+                (Some(source_end), Some(destination_end)) => {
+                    sources.push((row.line, source..source_end));
+                    destinations.push((row.line, destination..destination_end));
                 }
+                (None, _) => error(format!("source {} + {} overflows", source, length)),
+                (_, None) => error(format!(
+                    "destination {} + {} overflows",
+                    destination, length
+                )),
+            }
+        }
+
+        overlaps(&sources, &mut issues, |other_line, inter| {
+            format!("source range overlaps line {} on {:?}", other_line, inter)
+        });
+        overlaps(&destinations, &mut issues, |other_line, inter| {
+            format!(
+                "destination range overlaps line {} on {:?}, {} is not injective",
+                other_line,
+                inter,
+                block_name(block)
+            )
+        });
+
+        let covered: IntervalSet<i64> = sources.into_iter().map(|(_, range)| range).collect();
+        if let (Some(first), Some(last)) = (covered.min(), covered.max()) {
+            for gap in IntervalSet::from(first..last + 1)
+                .difference(&covered)
+                .ranges()
+            {
+                issues.push(Issue {
+                    line: block.line,
+                    severity: Severity::Note,
+                    message: format!(
+                        "{} doesn't cover {:?}, it maps to itself",
+                        block_name(block),
+                        gap
+                    ),
+                });
             }
         }
-        min_location = min(min_location, source);
     }
 
-    Ok(min_location)
+    issues.sort_by_key(|issue| issue.line);
+    Ok(issues)
 }
 
-trait RangeExt<T>
+fn block_name(block: &Block) -> String {
+    format!("{}-to-{}", block.source, block.destination)
+}
+
+fn overlaps<F>(ranges: &[(usize, Range<i64>)], issues: &mut Vec<Issue>, message: F)
 where
-    Self: Sized,
+    F: Fn(usize, Range<i64>) -> String,
 {
-    fn intersection(&self, other: &Self) -> Option<Self>;
-    fn cutouts(&self, other: &Self) -> Vec<Self>;
+    for (i, (line, range)) in ranges.iter().enumerate() {
+        for (other_line, other) in &ranges[i + 1..] {
+            if let Some(inter) = range.intersection(other) {
+                issues.push(Issue {
+                    line: *other_line,
+                    severity: Severity::Error,
+                    message: message(*line, inter),
+                });
+            }
+        }
+    }
+}
+
+pub fn print_check(lines: &[String]) -> Result<usize> {
+    let issues = check(lines)?;
 
-    fn add(self, rhs: T) -> Self;
+    for issue in &issues {
+        let severity = match issue.severity {
+            Severity::Error => "error",
+            Severity::Note => "note",
+        };
+        println!("line {}: {}: {}", issue.line, severity, issue.message);
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    println!("{} errors, {} notes", errors, issues.len() - errors);
+
+    Ok(errors)
 }
 
-impl RangeExt<i64> for Range<i64> {
-    fn intersection(&self, other: &Self) -> Option<Self> {
-        let left = max(self.start, other.start);
-        let right = min(self.end, other.end);
+impl FromStr for Almanac {
+    type Err = Error;
 
-        if left < right {
-            Some(left..right)
-        } else {
-            None
-        }
+    fn from_str(s: &str) -> Result<Almanac> {
+        Almanac::from_lines(&s.lines().collect::<Vec<_>>())
     }
+}
 
-    fn cutouts(&self, other: &Self) -> Vec<Self> {
-        if other.end <= self.start {
-            vec![self.start..self.end]
-        } else if other.start >= self.end {
-            vec![self.start..self.end]
-        } else if self.start < other.start && other.start < self.end && self.end <= other.end {
-            vec![self.start..other.start]
-        } else if self.start >= other.start && other.end > self.start && self.end > other.end {
-            vec![other.end..self.end]
-        } else if other.start > self.start && other.end < self.end {
-            vec![self.start..other.start, other.end..self.end]
-        } else {
-            vec![]
-        }
+// parses whitespace separated numbers of `line` starting from the byte `offset`
+fn parse_numbers(line_no: usize, line: &str, offset: usize) -> Result<Vec<i64>> {
+    let rest = &line[offset..];
+
+    rest.split_whitespace()
+        .map(|token| {
+            token.parse::<i64>().map_err(|_| {
+                let token_offset = offset + (token.as_ptr() as usize - rest.as_ptr() as usize);
+                Error::parse(
+                    line_no,
+                    column_at(line, token_offset),
+                    token,
+                    "expected a number",
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn solve_part1(almanac: &Almanac) -> Result<i64> {
+    let chain = almanac.chain()?;
+
+    let mut min_location = i64::MAX;
+    for &seed in &almanac.seeds {
+        min_location = min(min_location, chain.apply(seed));
     }
 
-    fn add(self, rhs: i64) -> Range<i64> {
-        self.start + rhs..self.end + rhs
+    Ok(min_location)
+}
+
+pub fn print_reverse(almanac: &Almanac, locations: Range<i64>) -> Result<()> {
+    let seeds = almanac.seeds_for(locations.clone())?;
+    println!(
+        "locations {:?} come from seeds {:?}",
+        locations,
+        seeds.ranges()
+    );
+    println!(
+        "seed ranges of the almanac among them: {:?}",
+        almanac.seed_set()?.intersection(&seeds).ranges()
+    );
+
+    match almanac.chain()?.invert() {
+        Some(inverse) => println!("{} has {} pieces", inverse.name(), inverse.pieces.len()),
+        None => println!("seed-to-location is not invertible"),
     }
+
+    Ok(())
 }
 
-//
-pub fn solve_part2_not_working(file: &mut io::BufReader<File>) -> Result<i64, Box<dyn Error>> {
-    let mut lines = file.lines();
-
-    let seeds: Vec<i64> = lines
-        .next()
-        .ok_or("empty file")??
-        .strip_prefix("seeds: ")
-        .ok_or("bad seeds line")?
-        .split_whitespace()
-        .filter_map(|s| s.parse::<i64>().ok())
+pub fn print_conversion(almanac: &Almanac, source: &str, destination: &str) -> Result<()> {
+    let route: Vec<String> = almanac
+        .route(source, destination)?
+        .iter()
+        .map(|section| section.describe())
         .collect();
-
-    if seeds.len() % 2 != 0 {
-        return Err("odd number of seeds")?;
+    println!("route: {}", route.join(" -> "));
+
+    let map = almanac.conversion(source, destination)?;
+    println!("{}:", map.name());
+    for piece in &map.pieces {
+        println!(
+            "  {:?} -> {:?}",
+            piece.range,
+            piece.range.clone().add(piece.offset)
+        );
     }
+    println!("  everything else maps to itself");
+
+    Ok(())
+}
 
-    let mut seeds_ranges: Vec<Range<i64>> = seeds
-        .chunks(2)
-        .map(|pair| pair[0]..pair[0] + pair[1])
+pub fn solve_part2(almanac: &Almanac) -> Result<i64> {
+    let chain = almanac.chain()?;
+
+    let locations: IntervalSet<i64> = almanac
+        .seed_set()?
+        .ranges()
+        .iter()
+        .flat_map(|range| chain.apply_range(range.clone()))
         .collect();
 
-    let mut maps: Vec<Map> = Vec::with_capacity(7);
-    let mut current_map: Option<Map> = None;
-    for line in lines {
-        let line = line?;
+    Ok(locations.min().unwrap_or(i64::MAX))
+}
 
-        if line.len() == 0 {
-            continue;
-        }
+// seeds a worker takes at a time
+const BRUTE_FORCE_CHUNK: i64 = 1 << 20;
 
-        if line.ends_with(':') {
-            if current_map.is_some() {
-                maps.push(current_map.unwrap());
-            }
+// checks every seed one by one through the maps of the route, independently of
+// the interval arithmetic used by `solve_part2`
+pub fn brute_force_part2(almanac: &Almanac, threads: usize, progress: bool) -> Result<i64> {
+    let maps: Vec<&Map> = almanac
+        .route("seed", "location")?
+        .into_iter()
+        .map(|section| &section.map)
+        .collect();
 
-            current_map = Some(Map {
-                name: line
-                    .strip_suffix(" map:")
-                    .ok_or("bad map name")?
-                    .to_string(),
-                source_range: Vec::new(),
-                offset: Vec::new(),
-            });
-            continue;
+    let seeds = almanac.seed_set()?;
+    let total = seeds.total_len();
+
+    let mut chunks: Vec<Range<i64>> = Vec::new();
+    for range in seeds.ranges() {
+        let mut start = range.start;
+        while start < range.end {
+            let end = min(start.saturating_add(BRUTE_FORCE_CHUNK), range.end);
+            chunks.push(start..end);
+            start = end;
         }
+    }
 
-        let nums: Vec<i64> = line
-            .split_whitespace()
-            .filter_map(|s| s.parse::<i64>().ok())
-            .collect();
+    let next = AtomicUsize::new(0);
+    let done = AtomicI64::new(0);
 
-        if nums.len() != 3 {
-            return Err("bad nums line")?;
+    let worker = || {
+        let mut min_location = i64::MAX;
+        while let Some(chunk) = chunks.get(next.fetch_add(1, Ordering::Relaxed)) {
+            for seed in chunk.clone() {
+                let location = maps.iter().fold(seed, |x, map| map.apply(x));
+                min_location = min(min_location, location);
+            }
+            done.fetch_add(chunk.end - chunk.start, Ordering::Relaxed);
         }
+        min_location
+    };
 
-        let current_map = current_map.as_mut().ok_or("missing map entry")?;
-        current_map.source_range.push(nums[1]..nums[1] + nums[2]);
-        current_map.offset.push(nums[0] - nums[1]);
-    }
-
-    if current_map.is_some() {
-        maps.push(current_map.unwrap());
-    }
-
-    let mut new_seed_ranges: Vec<Range<i64>> = Vec::new();
-    for map in maps {
-        loop {
-            match seeds_ranges.pop() {
-                Some(seed_range) => {
-                    for (i, map_range) in map.source_range.iter().enumerate() {
-                        match seed_range.intersection(map_range) {
-                            Some(inter) => {
-                                seeds_ranges.append(&mut seed_range.cutouts(&inter));
-                                new_seed_ranges.push(inter.add(map.offset[i]));
-                            }
-                            None => {
-                                if i == map.source_range.len() - 1 {
-                                    new_seed_ranges.push(seed_range.clone());
-                                }
-                            }
-                        }
-                    }
-                }
-                None => {
+    let min_location = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+
+        if progress {
+            loop {
+                let finished = workers.iter().all(|worker| worker.is_finished());
+                eprint!("\r{} / {} seeds", done.load(Ordering::Relaxed), total);
+                if finished {
                     break;
                 }
-            };
+                thread::sleep(Duration::from_millis(200));
+            }
+            eprintln!();
         }
-        seeds_ranges = new_seed_ranges;
-        new_seed_ranges = Vec::new();
-    }
 
-    let mut min_loc = i64::MAX;
-    for range in seeds_ranges {
-        if range.start < min_loc {
-            min_loc = range.start;
-        }
-    }
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("brute force worker panicked"))
+            .min()
+    });
+
+    Ok(min_location.unwrap_or(i64::MAX))
+}
 
-    Ok(min_loc)
+// returns whether the brute force agrees with the interval solver
+pub fn print_brute_force(almanac: &Almanac, threads: usize, progress: bool) -> Result<bool> {
+    let start = Instant::now();
+    let brute_force = brute_force_part2(almanac, threads, progress)?;
+    println!(
+        "brute force: {} ({} threads, {:.2?})",
+        brute_force,
+        threads,
+        start.elapsed()
+    );
+
+    let intervals = solve_part2(almanac)?;
+    println!("intervals:   {}", intervals);
+
+    if brute_force != intervals {
+        println!("MISMATCH");
+    }
+    Ok(brute_force == intervals)
 }
 
 #[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
 mod tests {
     use super::*;
+    use crate::solution::check_examples;
+
     #[test]
-    fn add() {
-        let range = 1..3;
-        let res = range.add(5);
-        assert_eq!(res, 6..8);
+    fn examples() {
+        check_examples::<Day5>();
     }
 
     #[test]
-    fn intersection() {
-        let range = 3..8;
-
-        let inter = range.intersection(&(3_i64..8_i64));
-        assert_eq!(inter, Some(3..8));
-
-        let inter = range.intersection(&(0_i64..2_i64));
-        assert_eq!(inter, None);
+    fn brute_force() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        for threads in 1..4 {
+            assert_eq!(brute_force_part2(&almanac, threads, false).unwrap(), 46);
+        }
 
-        let inter = range.intersection(&(0_i64..3_i64));
-        assert_eq!(inter, None);
+        let almanac: Almanac =
+            "seeds: 5 10 2000000 3000000\n\nseed-to-soil map:\n100 8 2\n\nsoil-to-location map:\n0 100 1\n1 4999999 1\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            brute_force_part2(&almanac, 4, false).unwrap(),
+            solve_part2(&almanac).unwrap()
+        );
+    }
 
-        let inter = range.intersection(&(0_i64..4_i64));
-        assert_eq!(inter, Some(3..4));
+    #[test]
+    fn part2_keeps_unmapped_remainders() {
+        let almanac: Almanac =
+            "seeds: 5 10\n\nseed-to-soil map:\n100 8 2\n\nsoil-to-location map:\n0 100 1\n"
+                .parse()
+                .unwrap();
+
+        // 5..15 -> 5..8, 100..102, 10..15 -> 5..8, 0..1, 101..102, 10..15
+        assert_eq!(solve_part2(&almanac).unwrap(), 0);
+
+        let almanac: Almanac = "seeds: 5 10\n\nseed-to-location map:\n100 0 7\n"
+            .parse()
+            .unwrap();
+        assert_eq!(solve_part2(&almanac).unwrap(), 7);
+    }
 
-        let inter = range.intersection(&(3_i64..6_i64));
-        assert_eq!(inter, Some(3..6));
+    #[test]
+    fn almanac() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.sections.len(), 7);
+        assert_eq!(almanac.sections[0].line, 3);
+        assert_eq!(almanac.sections[0].map.name(), "seed-to-soil");
+        assert_eq!(
+            almanac.sections[0].map.pieces,
+            vec![
+                Piece {
+                    range: 50..98,
+                    offset: 2
+                },
+                Piece {
+                    range: 98..100,
+                    offset: -48
+                }
+            ]
+        );
+    }
 
-        let inter = range.intersection(&(5_i64..6_i64));
-        assert_eq!(inter, Some(5..6));
+    #[test]
+    fn map_first_entry_wins() {
+        let map = Map::new(
+            "a",
+            "b",
+            &[(0..10, 0), (5..15, 100), (20..30, 5), (30..40, 5)],
+        );
+        assert_eq!(
+            map.pieces,
+            vec![
+                Piece {
+                    range: 10..15,
+                    offset: 100
+                },
+                Piece {
+                    range: 20..40,
+                    offset: 5
+                }
+            ]
+        );
+        assert_eq!(map.apply(7), 7);
+        assert_eq!(map.apply(12), 112);
+        assert_eq!(map.apply(15), 15);
+        assert_eq!(map.apply(39), 44);
+    }
 
-        let inter = range.intersection(&(5_i64..8_i64));
-        assert_eq!(inter, Some(5..8));
+    #[test]
+    fn map_apply_range() {
+        let map = Map::new("a", "b", &[(10..15, 100), (20..30, -20)]);
+        assert_eq!(map.apply_range(0..5), vec![0..5]);
+        assert_eq!(map.apply_range(5..25), vec![5..10, 110..115, 15..20, 0..5]);
+        assert_eq!(map.apply_range(12..13), vec![112..113]);
+    }
 
-        let inter = range.intersection(&(5_i64..10_i64));
-        assert_eq!(inter, Some(5..8));
+    #[test]
+    fn map_compose() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        let chain = almanac.chain().unwrap();
+        assert_eq!(chain.name(), "seed-to-location");
+
+        for seed in -5..120 {
+            let expected = almanac
+                .sections
+                .iter()
+                .fold(seed, |x, section| section.map.apply(x));
+            assert_eq!(chain.apply(seed), expected, "seed {}", seed);
+        }
 
-        let inter = range.intersection(&(8_i64..10_i64));
-        assert_eq!(inter, None);
+        let identity = Map::identity("soil");
+        assert_eq!(
+            almanac.sections[0].map.compose(&identity).pieces,
+            almanac.sections[0].map.pieces
+        );
+
+        // 0..10 -> 5..15 -> 0..10, while 10..15 passes the first map unchanged
+        let map = Map::new("a", "b", &[(0..10, 5)]);
+        let other = Map::new("b", "c", &[(5..15, -5)]);
+        assert_eq!(
+            map.compose(&other).pieces,
+            vec![Piece {
+                range: 10..15,
+                offset: -5
+            }]
+        );
     }
 
     #[test]
-    fn cutouts() {
-        let range = 3..8;
+    fn map_invert() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        for map in almanac.sections.iter().map(|section| &section.map) {
+            let inverse = map.invert().unwrap();
+            assert_eq!(map.compose(&inverse).pieces, vec![]);
+            assert_eq!(inverse.compose(map).pieces, vec![]);
+        }
 
-        let inter = range.cutouts(&(3_i64..8_i64));
-        assert_eq!(inter, vec![]);
+        let inverse = almanac.chain().unwrap().invert().unwrap();
+        assert_eq!(inverse.name(), "location-to-seed");
+        assert_eq!(inverse.apply(46), 82);
+        assert_eq!(inverse.apply(35), 13);
+
+        // 0..10 -> 100..110 leaves 0..10 without preimage and 100..110 with two
+        let map = Map::new("a", "b", &[(0..10, 100)]);
+        assert_eq!(map.invert(), None);
+        assert_eq!(
+            map.preimage(&IntervalSet::from(105..106)).ranges(),
+            &[5..6, 105..106]
+        );
+        assert_eq!(map.preimage(&IntervalSet::from(5..8)).ranges(), &[]);
+    }
 
-        let inter = range.cutouts(&(0_i64..2_i64));
-        assert_eq!(inter, vec![3..8]);
+    #[test]
+    fn seeds_for() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        assert_eq!(almanac.seeds_for(46..47).unwrap().ranges(), &[82..83]);
+
+        let chain = almanac.chain().unwrap();
+        for range in almanac.seeds_for(40..60).unwrap().ranges() {
+            for seed in range.clone() {
+                let location = chain.apply(seed);
+                assert!((40..60).contains(&location));
+            }
+        }
+    }
 
-        let inter = range.cutouts(&(0_i64..3_i64));
-        assert_eq!(inter, vec![3..8]);
+    #[test]
+    fn route() {
+        let almanac: Almanac = EXAMPLE.parse().unwrap();
+        let map = almanac.conversion("soil", "humidity").unwrap();
+        assert_eq!(map.name(), "soil-to-humidity");
+        // soil 81, fertilizer 81, water 81, light 74, temperature 78, humidity 78
+        assert_eq!(map.apply(81), 78);
+
+        let err = almanac.conversion("location", "seed").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no conversion from location to seed: no map converts from location"
+        );
+
+        let err = almanac.conversion("humidity", "seed").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no conversion from humidity to seed: no map converts to seed"
+        );
+
+        let err = almanac.conversion("water", "soil").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no conversion from water to soil: maps from water only reach light, temperature, humidity, location"
+        );
+    }
 
-        let inter = range.cutouts(&(0_i64..4_i64));
-        assert_eq!(inter, vec![4..8]);
+    #[test]
+    fn route_ambiguous() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n1 2 3\n\nsoil-to-location map:\n1 2 3\n\nseed-to-location map:\n1 2 3\n";
+        let almanac: Almanac = input.parse().unwrap();
+
+        let err = almanac.chain().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no conversion from seed to location: ambiguous, either seed-to-soil (line 3) -> soil-to-location (line 6) or seed-to-location (line 9)"
+        );
+        assert!(almanac.conversion("soil", "location").is_ok());
+    }
 
-        let inter = range.cutouts(&(3_i64..6_i64));
-        assert_eq!(inter, vec![6..8]);
+    #[test]
+    fn route_out_of_file_order() {
+        let input = "seeds: 1 2\n\nsoil-to-location map:\n100 0 10\n\nseed-to-soil map:\n0 1 1\n";
+        let almanac: Almanac = input.parse().unwrap();
+        assert_eq!(solve_part1(&almanac).unwrap(), 100);
+    }
 
-        let inter = range.cutouts(&(5_i64..6_i64));
-        assert_eq!(inter, vec![3..5, 6..8]);
+    #[test]
+    fn check_example() {
+        let issues = check(&EXAMPLE.lines().collect::<Vec<_>>()).unwrap();
+        assert_eq!(issues, vec![]);
+    }
 
-        let inter = range.cutouts(&(5_i64..8_i64));
-        assert_eq!(inter, vec![3..5]);
+    #[test]
+    fn check_errors() {
+        let input = "seeds: 1 2\n\na-to-b map:\n0 10 10\n100 15 10\n5 -3 -1\n0 9223372036854775800 10\n\nb-to-c map:\n0 0 5\n3 10 5\n";
+        let issues = check(&input.lines().collect::<Vec<_>>()).unwrap();
+        let messages: Vec<(usize, &str)> = issues
+            .iter()
+            .map(|issue| (issue.line, issue.message.as_str()))
+            .collect();
 
-        let inter = range.cutouts(&(5_i64..10_i64));
-        assert_eq!(inter, vec![3..5]);
+        assert_eq!(
+            messages,
+            vec![
+                (5, "source range overlaps line 4 on 15..20"),
+                (6, "negative length -1"),
+                (7, "source 9223372036854775800 + 10 overflows"),
+                (9, "b-to-c doesn't cover 5..10, it maps to itself"),
+                (
+                    11,
+                    "destination range overlaps line 10 on 3..5, b-to-c is not injective"
+                ),
+            ]
+            .into_iter()
+            .collect::<Vec<_>>()
+        );
+    }
 
-        let inter = range.cutouts(&(8_i64..10_i64));
-        assert_eq!(inter, vec![3..8]);
+    #[test]
+    fn almanac_errors() {
+        let err = "".parse::<Almanac>().unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 1: empty input: \"\"");
+
+        let err = "soil: 1 2\n".parse::<Almanac>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 1: expected 'seeds:': \"soil: 1 2\""
+        );
+
+        let err = "seeds: 1 x2\n".parse::<Almanac>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 10: expected a number: \"x2\""
+        );
+
+        let err = "seeds: 1 2\n\n1 2 3\n".parse::<Almanac>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: missing map header: \"1 2 3\""
+        );
+
+        let err = "seeds: 1 2\n\na-to-b:\n".parse::<Almanac>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: expected '<source>-to-<destination> map:': \"a-to-b:\""
+        );
+
+        let err = "seeds: 1 2\n\na-to-b map:\n1 2 3 4\n"
+            .parse::<Almanac>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 1: expected 3 numbers, found 4: \"1 2 3 4\""
+        );
+
+        let err = "seeds: 1 2\n\na-to-b map:\n1 -2 a3\n"
+            .parse::<Almanac>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 6: expected a number: \"a3\""
+        );
+
+        let err = "seeds: 1 2\n\na-to-b map:\n1 2 -3\n"
+            .parse::<Almanac>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 1: negative length -3: \"1 2 -3\""
+        );
+
+        let err = "seeds: 1 2\n\na-to-b map:\n1 -100 3\n"
+            .parse::<Almanac>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 1: negative start of source -100: \"1 -100 3\""
+        );
+    }
 
-        let inter = range.cutouts(&(9_i64..10_i64));
-        assert_eq!(inter, vec![3..8]);
+    #[test]
+    fn seed_set() {
+        // two valid seeds for part 1, but not a range for part 2
+        let almanac: Almanac =
+            "seeds: 1 2 9223372036854775800 100\n\nseed-to-location map:\n0 1 1\n"
+                .parse()
+                .unwrap();
+        assert_eq!(solve_part1(&almanac).unwrap(), 0);
+        assert_eq!(
+            almanac.seed_set().unwrap_err().to_string(),
+            "line 1, column 1: seed range 9223372036854775800 + 100 overflows: \"seeds: 1 2 9223372036854775800 100\""
+        );
+        assert!(check(&["seeds: 9223372036854775800 100"])
+            .unwrap()
+            .is_empty());
+
+        let almanac: Almanac = "seeds: 5 -1\n".parse().unwrap();
+        assert_eq!(
+            almanac.seed_set().unwrap_err().to_string(),
+            "line 1, column 1: negative seed range length -1: \"seeds: 5 -1\""
+        );
+        let almanac: Almanac = "seeds: -5 1\n".parse().unwrap();
+        assert!(almanac.seed_set().is_err());
     }
 }
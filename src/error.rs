@@ -0,0 +1,137 @@
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// 1-based position of the offending text in the puzzle input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        line: Option<usize>,
+        source: io::Error,
+    },
+    Parse {
+        location: Location,
+        message: String,
+    },
+    Semantic {
+        location: Location,
+        message: String,
+    },
+    UnknownPart(u8),
+    AnswerOverflow(u64),
+    Route {
+        source: String,
+        destination: String,
+        message: String,
+    },
+}
+
+impl Error {
+    pub fn parse(line: usize, column: usize, text: &str, message: impl Into<String>) -> Self {
+        Error::Parse {
+            location: Location {
+                line,
+                column,
+                text: text.to_string(),
+            },
+            message: message.into(),
+        }
+    }
+
+    pub fn semantic(line: usize, column: usize, text: &str, message: impl Into<String>) -> Self {
+        Error::Semantic {
+            location: Location {
+                line,
+                column,
+                text: text.to_string(),
+            },
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io {
+                line: Some(line),
+                source,
+            } => write!(f, "line {}: read error: {}", line, source),
+            Error::Io { line: None, source } => write!(f, "read error: {}", source),
+            Error::Parse { location, message } => write!(
+                f,
+                "line {}, column {}: {}: {:?}",
+                location.line, location.column, message, location.text
+            ),
+            Error::Semantic { location, message } => write!(
+                f,
+                "line {}, column {}: {}: {:?}",
+                location.line, location.column, message, location.text
+            ),
+            Error::UnknownPart(part) => write!(f, "unknown part {}", part),
+            Error::AnswerOverflow(answer) => write!(f, "answer {} does not fit in i64", answer),
+            Error::Route {
+                source,
+                destination,
+                message,
+            } => write!(
+                f,
+                "no conversion from {} to {}: {}",
+                source, destination, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// 1-based column of the byte `offset` inside `line`, an offset inside a
+// multibyte character counts as that character
+pub fn column_at(line: &str, offset: usize) -> usize {
+    line[..line.floor_char_boundary(offset)].chars().count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let err = Error::parse(3, 7, "12x", "bad number");
+        assert_eq!(err.to_string(), "line 3, column 7: bad number: \"12x\"");
+
+        let err = Error::semantic(1, 1, "seeds: 1", "odd number of seeds");
+        assert!(matches!(
+            err,
+            Error::Semantic { location, .. } if location == Location {
+                line: 1,
+                column: 1,
+                text: "seeds: 1".to_string()
+            }
+        ));
+    }
+
+    #[test]
+    fn column() {
+        assert_eq!(column_at("seeds: 79 x4", 10), 11);
+        assert_eq!(column_at("seeds: 79 x4", 0), 1);
+        assert_eq!(column_at("é1", 2), 2);
+        assert_eq!(column_at("ab", 10), 3);
+        assert_eq!(column_at("éé", 3), 2);
+    }
+}
@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+
+use crate::error::{column_at, Error, Result};
+
+const OFFSETS_4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const OFFSETS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// a run of ascii digits within a row, the columns are col_start..col_end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Number {
+    pub value: u64,
+    pub row: usize,
+    pub col_start: usize,
+    pub col_end: usize,
+}
+
+// rectangular grid stored row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl Grid<u8> {
+    // one row per line, every line must be as long as the first one
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Result<Grid<u8>> {
+        let first = lines
+            .first()
+            .ok_or_else(|| Error::parse(1, 1, "", "empty input"))?;
+        let width = first.as_ref().len();
+
+        let mut cells: Vec<u8> = Vec::with_capacity(width * lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            let line = line.as_ref();
+            if line.len() != width {
+                return Err(Error::parse(
+                    i + 1,
+                    column_at(line, width.min(line.len())),
+                    line,
+                    format!("row has {} columns, expected {}", line.len(), width),
+                ));
+            }
+            cells.extend_from_slice(line.as_bytes());
+        }
+
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    // every number of the grid, row by row. The digits are read as written, so
+    // "007" is 7 and "0" is a number too.
+    pub fn numbers(&self) -> Result<Vec<Number>> {
+        let mut numbers: Vec<Number> = Vec::new();
+        for (row, cells) in self.rows().enumerate() {
+            let mut col = 0;
+            while col < cells.len() {
+                if !cells[col].is_ascii_digit() {
+                    col += 1;
+                    continue;
+                }
+
+                let col_start = col;
+                let mut value: Option<u64> = Some(0);
+                while col < cells.len() && cells[col].is_ascii_digit() {
+                    value = value
+                        .and_then(|value| value.checked_mul(10))
+                        .and_then(|value| value.checked_add(u64::from(cells[col] - b'0')));
+                    col += 1;
+                }
+                let value = value.ok_or_else(|| {
+                    let line = self.line(row);
+                    Error::parse(
+                        row + 1,
+                        column_at(&line, col_start),
+                        &line,
+                        "number does not fit in 64 bits",
+                    )
+                })?;
+                numbers.push(Number {
+                    value,
+                    row,
+                    col_start,
+                    col_end: col,
+                });
+            }
+        }
+
+        Ok(numbers)
+    }
+
+    // error at a cell, located on the input line the row was read from
+    pub fn error(&self, row: usize, col: usize, message: impl Into<String>) -> Error {
+        let line = self.line(row);
+        Error::semantic(row + 1, column_at(&line, col), &line, message)
+    }
+
+    fn line(&self, row: usize) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.row(row).unwrap_or_default())
+    }
+}
+
+impl<T> Grid<T> {
+    #[allow(dead_code)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[allow(dead_code)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row < self.height && col < self.width {
+            self.cells.get(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row < self.height {
+            Some(&self.cells[row * self.width..(row + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).filter_map(|row| self.row(row))
+    }
+
+    #[allow(dead_code)]
+    pub fn column(&self, col: usize) -> Option<impl Iterator<Item = &T>> {
+        if col < self.width {
+            Some((0..self.height).map(move |row| &self.cells[row * self.width + col]))
+        } else {
+            None
+        }
+    }
+
+    fn offsets<'a>(
+        &'a self,
+        row: usize,
+        col: usize,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let row = row.checked_add_signed(dr)?;
+            let col = col.checked_add_signed(dc)?;
+            self.get(row, col).map(|_| (row, col))
+        })
+    }
+
+    // positions of the edge neighbors inside the grid
+    #[allow(dead_code)]
+    pub fn neighbors4(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(row, col, &OFFSETS_4)
+    }
+
+    // positions of the edge and corner neighbors inside the grid
+    pub fn neighbors8(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(row, col, &OFFSETS_8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid() {
+        let grid = Grid::parse(&["abc", "def"]).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(1, 2), Some(&b'f'));
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.rows().collect::<Vec<_>>(), vec![b"abc", b"def"]);
+        assert_eq!(grid.row(1), Some(&b"def"[..]));
+        assert_eq!(grid.row(2), None);
+        assert_eq!(grid.column(1).unwrap().copied().collect::<Vec<_>>(), b"be");
+        assert!(grid.column(3).is_none());
+
+        let mut neighbors: Vec<_> = grid.neighbors8(0, 0).collect();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(grid.neighbors8(1, 1).count(), 5);
+        assert_eq!(
+            grid.neighbors4(1, 1).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let grid = Grid::parse(&["467..007", "0.*12...", "........"]).unwrap();
+        let numbers: Vec<(u64, usize, usize, usize)> = grid
+            .numbers()
+            .unwrap()
+            .into_iter()
+            .map(|n| (n.value, n.row, n.col_start, n.col_end))
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![(467, 0, 0, 3), (7, 0, 5, 8), (0, 1, 0, 1), (12, 1, 3, 5)]
+        );
+
+        let grid = Grid::parse(&["18446744073709551615..", "..18446744073709551616"]).unwrap();
+        let err = grid.numbers().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 3: number does not fit in 64 bits: \"..18446744073709551616\""
+        );
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let err = Grid::parse(&["abc", "de", "fgh"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 3: row has 2 columns, expected 3: \"de\""
+        );
+
+        let err = Grid::parse(&["abc", "defg"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 4: row has 4 columns, expected 3: \"defg\""
+        );
+
+        // widths are in bytes, the column points at the character
+        let err = Grid::parse(&["abc", "éé"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 2: row has 4 columns, expected 3: \"éé\""
+        );
+
+        assert!(Grid::parse::<&str>(&[]).is_err());
+    }
+}
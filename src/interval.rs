@@ -0,0 +1,356 @@
+use std::cmp::{max, min};
+use std::ops::Range;
+
+use num_traits::PrimInt;
+
+pub trait RangeExt<T>
+where
+    Self: Sized,
+{
+    fn intersection(&self, other: &Self) -> Option<Self>;
+    fn cutouts(&self, other: &Self) -> Vec<Self>;
+
+    fn add(self, rhs: T) -> Self;
+}
+
+#[allow(clippy::single_range_in_vec_init)]
+impl<T: PrimInt> RangeExt<T> for Range<T> {
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let left = max(self.start, other.start);
+        let right = min(self.end, other.end);
+
+        if left < right {
+            Some(left..right)
+        } else {
+            None
+        }
+    }
+
+    fn cutouts(&self, other: &Self) -> Vec<Self> {
+        if other.end <= self.start || other.start >= self.end {
+            vec![self.start..self.end]
+        } else if self.start < other.start && other.start < self.end && self.end <= other.end {
+            vec![self.start..other.start]
+        } else if self.start >= other.start && other.end > self.start && self.end > other.end {
+            vec![other.end..self.end]
+        } else if other.start > self.start && other.end < self.end {
+            vec![self.start..other.start, other.end..self.end]
+        } else {
+            vec![]
+        }
+    }
+
+    fn add(self, rhs: T) -> Range<T> {
+        self.start + rhs..self.end + rhs
+    }
+}
+
+// a set of integers stored as sorted, non-empty ranges with gaps between them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        self.ranges
+            .iter()
+            .chain(other.ranges.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.ranges.len() && j < other.ranges.len() {
+            if let Some(inter) = self.ranges[i].intersection(&other.ranges[j]) {
+                ranges.push(inter);
+            }
+            if self.ranges[i].end < other.ranges[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        // both sides are disjoint, so are the intersections
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+
+        for range in &self.ranges {
+            let mut rest = range.clone();
+            let first = other
+                .ranges
+                .partition_point(|other| other.end <= rest.start);
+            for other in &other.ranges[first..] {
+                if other.start >= rest.end {
+                    break;
+                }
+                if rest.start < other.start {
+                    ranges.push(rest.start..other.start);
+                }
+                rest.start = max(rest.start, other.end);
+            }
+            if rest.start < rest.end {
+                ranges.push(rest);
+            }
+        }
+
+        IntervalSet { ranges }
+    }
+
+    pub fn shift(&self, delta: T) -> IntervalSet<T> {
+        IntervalSet {
+            ranges: self
+                .ranges
+                .iter()
+                .map(|range| range.clone().add(delta))
+                .collect(),
+        }
+    }
+
+    // number of integers in the set
+    pub fn total_len(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |len, range| len + (range.end - range.start))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|range| range.end - T::one())
+    }
+}
+
+impl<T: PrimInt> Default for IntervalSet<T> {
+    fn default() -> IntervalSet<T> {
+        IntervalSet::new()
+    }
+}
+
+impl<T: PrimInt> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> IntervalSet<T> {
+        IntervalSet::from_iter([range])
+    }
+}
+
+// sorts the ranges and merges the overlapping or touching ones
+impl<T: PrimInt> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> IntervalSet<T> {
+        let mut ranges: Vec<Range<T>> = iter.into_iter().filter(|r| r.start < r.end).collect();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut res: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match res.last_mut() {
+                Some(last) if range.start <= last.end => last.end = max(last.end, range.end),
+                _ => res.push(range),
+            }
+        }
+
+        IntervalSet { ranges: res }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add() {
+        let range = 1..3;
+        let res = range.add(5);
+        assert_eq!(res, 6..8);
+    }
+
+    #[test]
+    fn intersection() {
+        let range = 3..8;
+
+        let inter = range.intersection(&(3_i64..8_i64));
+        assert_eq!(inter, Some(3..8));
+
+        let inter = range.intersection(&(0_i64..2_i64));
+        assert_eq!(inter, None);
+
+        let inter = range.intersection(&(0_i64..3_i64));
+        assert_eq!(inter, None);
+
+        let inter = range.intersection(&(0_i64..4_i64));
+        assert_eq!(inter, Some(3..4));
+
+        let inter = range.intersection(&(3_i64..6_i64));
+        assert_eq!(inter, Some(3..6));
+
+        let inter = range.intersection(&(5_i64..6_i64));
+        assert_eq!(inter, Some(5..6));
+
+        let inter = range.intersection(&(5_i64..8_i64));
+        assert_eq!(inter, Some(5..8));
+
+        let inter = range.intersection(&(5_i64..10_i64));
+        assert_eq!(inter, Some(5..8));
+
+        let inter = range.intersection(&(8_i64..10_i64));
+        assert_eq!(inter, None);
+    }
+
+    #[test]
+    fn cutouts() {
+        let range = 3..8;
+
+        let inter = range.cutouts(&(3_i64..8_i64));
+        assert_eq!(inter, vec![]);
+
+        let inter = range.cutouts(&(0_i64..2_i64));
+        assert_eq!(inter, vec![3..8]);
+
+        let inter = range.cutouts(&(0_i64..3_i64));
+        assert_eq!(inter, vec![3..8]);
+
+        let inter = range.cutouts(&(0_i64..4_i64));
+        assert_eq!(inter, vec![4..8]);
+
+        let inter = range.cutouts(&(3_i64..6_i64));
+        assert_eq!(inter, vec![6..8]);
+
+        let inter = range.cutouts(&(5_i64..6_i64));
+        assert_eq!(inter, vec![3..5, 6..8]);
+
+        let inter = range.cutouts(&(5_i64..8_i64));
+        assert_eq!(inter, vec![3..5]);
+
+        let inter = range.cutouts(&(5_i64..10_i64));
+        assert_eq!(inter, vec![3..5]);
+
+        let inter = range.cutouts(&(8_i64..10_i64));
+        assert_eq!(inter, vec![3..8]);
+
+        let inter = range.cutouts(&(9_i64..10_i64));
+        assert_eq!(inter, vec![3..8]);
+    }
+
+    #[test]
+    fn interval_set() {
+        let set: IntervalSet<i64> = [5..8, 1..3, 3..4, 7..10, 12..12].into_iter().collect();
+        assert_eq!(set.ranges(), &[1..4, 5..10]);
+        assert_eq!(set.total_len(), 8);
+        assert_eq!(set.min(), Some(1));
+        assert_eq!(set.max(), Some(9));
+
+        let other = IntervalSet::from(2..6);
+        assert_eq!(set.union(&other).ranges(), &[1..10]);
+        assert_eq!(set.intersection(&other).ranges(), &[2..4, 5..6]);
+        assert_eq!(set.difference(&other).ranges(), &[1..2, 6..10]);
+        assert_eq!(set.shift(-1).ranges(), &[0..3, 4..9]);
+
+        let empty: IntervalSet<i64> = IntervalSet::new();
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+        assert_eq!(empty.total_len(), 0);
+    }
+
+    // the sets under test only hold values of 0..64, so a u64 bitset models them exactly
+    const BITS: u32 = 64;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn set<T: PrimInt>(&mut self, max_len: u64) -> IntervalSet<T> {
+            let count = self.below(6);
+            (0..count)
+                .map(|_| {
+                    let start = self.below(BITS as u64 - max_len);
+                    let len = self.below(max_len + 1);
+                    T::from(start).unwrap()..T::from(start + len).unwrap()
+                })
+                .collect()
+        }
+    }
+
+    fn bits<T: PrimInt>(set: &IntervalSet<T>) -> u64 {
+        set.ranges().iter().fold(0, |bits, range| {
+            (range.start.to_u32().unwrap()..range.end.to_u32().unwrap())
+                .fold(bits, |bits, i| bits | (1 << i))
+        })
+    }
+
+    fn assert_normalized<T: PrimInt + std::fmt::Debug>(set: &IntervalSet<T>) {
+        for range in set.ranges() {
+            assert!(range.start < range.end, "empty range in {:?}", set);
+        }
+        for pair in set.ranges().windows(2) {
+            assert!(pair[0].end < pair[1].start, "unmerged ranges in {:?}", set);
+        }
+    }
+
+    fn check_model<T: PrimInt + std::fmt::Debug>(seed: u64) {
+        let mut rng = Rng(seed);
+
+        for _ in 0..2000 {
+            let a: IntervalSet<T> = rng.set(12);
+            let b: IntervalSet<T> = rng.set(12);
+            let (x, y) = (bits(&a), bits(&b));
+
+            let results = [
+                (a.union(&b), x | y),
+                (a.intersection(&b), x & y),
+                (a.difference(&b), x & !y),
+            ];
+            for (set, model) in &results {
+                assert_normalized(set);
+                assert_eq!(bits(set), *model, "{:?} and {:?}", a, b);
+            }
+
+            assert_normalized(&a);
+            assert_eq!(a.total_len().to_u32().unwrap(), x.count_ones());
+            assert_eq!(
+                a.min().map(|min| min.to_u32().unwrap()),
+                (x != 0).then_some(x.trailing_zeros())
+            );
+            assert_eq!(a.max().map(|max| max.to_u32().unwrap()), x.checked_ilog2());
+
+            // only shift within 0..64
+            let delta = rng.below(BITS as u64 - a.max().map_or(0, |max| max.to_u64().unwrap()));
+            let shifted = a.shift(T::from(delta).unwrap());
+            assert_normalized(&shifted);
+            assert_eq!(bits(&shifted), x << delta);
+        }
+    }
+
+    #[test]
+    fn interval_set_model() {
+        check_model::<i64>(0x2545_f491_4f6c_dd1d);
+        check_model::<u8>(0x9e37_79b9_7f4a_7c15);
+        check_model::<i16>(0xdead_beef_cafe_f00d);
+    }
+}
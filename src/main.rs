@@ -1,40 +1,241 @@
+mod answers;
+mod cli;
+mod day1;
+mod day2;
+mod day3;
+mod day4;
 mod day5;
+mod error;
+mod grid;
+mod interval;
+mod runner;
+mod solution;
 
+use std::env;
 use std::fs::File;
-use std::io::{self};
-use std::path::Path;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+
+use cli::Command;
+use solution::Solution;
 
 fn main() {
-    // match read_lines("./input/day1.txt") {
-    //     Ok(mut buf) => println!("{}", day1::day1_part2(&mut buf)),
-    //     Err(err) => println!("open file: {}", err),
-    // }
-
-    // match read_lines("./input/day2.txt") {
-    //     Ok(mut buf) => println!("{}", day2::day2_part2(&mut buf)),
-    //     Err(err) => println!("open file: {}", err),
-    // }
-
-    // match read_lines("./input/day3.txt") {
-    //     Ok(mut buf) => println!("{:?}", day3::day3_part2(&mut buf)),
-    //     Err(err) => println!("open file: {}", err),
-    // }
-
-    // match read_lines("./input/day4.txt") {
-    //     Ok(mut buf) => println!("{:?}", day4::day4_part2(&mut buf)),
-    //     Err(err) => println!("open file: {}", err),
-    // }
-
-    match read_lines("./input/day5.txt") {
-        Ok(mut buf) => println!("{:?}", day5::solve_part2(&mut buf)),
-        Err(err) => println!("open file: {}", err),
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Run {
+            day,
+            part,
+            input,
+            example,
+        } => {
+            let Some(solution) = solution::get(day) else {
+                let days: Vec<String> = solution::days().map(|day| day.to_string()).collect();
+                eprintln!(
+                    "unknown day {}: available days are {}",
+                    day,
+                    days.join(", ")
+                );
+                process::exit(2);
+            };
+            if !solution::PARTS.contains(&part) {
+                eprintln!("unknown part {}: available parts are 1 and 2", part);
+                process::exit(2);
+            }
+
+            if example {
+                if !runner::run_examples(solution, part) {
+                    process::exit(1);
+                }
+                return;
+            }
+
+            let path = input.unwrap_or_else(|| runner::default_input(day));
+            let mut buf = match runner::open_input(&path) {
+                Ok(buf) => buf,
+                Err(err) => {
+                    eprintln!("open file {}: {}", path.display(), err);
+                    process::exit(1);
+                }
+            };
+
+            match runner::run(solution, part, &mut buf).answer {
+                Ok(answer) => println!("{}", answer),
+                Err(err) => {
+                    eprintln!("day {} part {}: {}", day, part, err);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::All => {
+            if runner::run_all() > 0 {
+                process::exit(1);
+            }
+        }
+        Command::Reverse { locations, input } => {
+            let almanac = parse_or_exit::<day5::Day5>(5, input);
+            if let Err(err) = day5::print_reverse(&almanac, locations) {
+                eprintln!("day 5: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::Convert { from, to, input } => {
+            let almanac = parse_or_exit::<day5::Day5>(5, input);
+            if let Err(err) = day5::print_conversion(&almanac, &from, &to) {
+                eprintln!("day 5: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::BruteForce {
+            threads,
+            progress,
+            input,
+        } => {
+            let threads = threads.unwrap_or_else(|| {
+                thread::available_parallelism().map_or(1, |threads| threads.get())
+            });
+            let almanac = parse_or_exit::<day5::Day5>(5, input);
+            match day5::print_brute_force(&almanac, threads, progress) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(err) => {
+                    eprintln!("day 5: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Calibrate {
+            part,
+            vocabulary,
+            words,
+            skip_empty,
+            explain,
+            input,
+        } => {
+            let mut vocabulary = match vocabulary {
+                Some(path) => match load_vocabulary(&path) {
+                    Ok(vocabulary) => vocabulary,
+                    Err(err) => {
+                        eprintln!("vocabulary {}: {}", path.display(), err);
+                        process::exit(1);
+                    }
+                },
+                None if part == 1 => day1::Vocabulary::digits(),
+                None => day1::Vocabulary::english(),
+            };
+            vocabulary.extend(words);
+
+            let lines = parse_or_exit::<day1::Day1>(1, input);
+            let res = match (part, explain) {
+                (_, true) => day1::print_explain(&lines, &vocabulary, skip_empty),
+                (1, false) => day1::day1_part1(&lines, skip_empty).map(|sum| println!("{}", sum)),
+                (_, false) => {
+                    day1::day1_part2(&lines, &vocabulary, skip_empty).map(|sum| println!("{}", sum))
+                }
+            };
+            if let Err(err) = res {
+                eprintln!("day 1: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::Bench {
+            lines,
+            length,
+            spacing,
+        } => {
+            if !day1::print_bench(lines, length, spacing) {
+                process::exit(1);
+            }
+        }
+        Command::Possible { bag, input } => {
+            let bag = bag.map_or_else(day2::default_bag, |bag| bag.into_iter().collect());
+            let games = games_or_exit(input, &bag);
+            if let Err(err) = day2::print_possible(&games, &bag) {
+                eprintln!("day 2: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::Report { bag, input } => {
+            let bag = bag.map_or_else(day2::default_bag, |bag| bag.into_iter().collect());
+            let games = games_or_exit(input, &bag);
+            if let Err(err) = day2::print_report(&games, &bag) {
+                eprintln!("day 2: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::Infer {
+            bag,
+            max_count,
+            input,
+        } => {
+            let bag = bag.map_or_else(day2::default_bag, |bag| bag.into_iter().collect());
+            let games = games_or_exit(input, &bag);
+            if let Err(err) = day2::print_inference(&games, &bag, max_count) {
+                eprintln!("day 2: {}", err);
+                process::exit(1);
+            }
+        }
+        Command::Check { input } => {
+            let errors = solution::read_lines(open_or_exit(5, input))
+                .and_then(|lines| day5::print_check(&lines));
+            match errors {
+                Ok(0) => {}
+                Ok(_) => process::exit(1),
+                Err(err) => {
+                    eprintln!("day 5: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn load_vocabulary(path: &Path) -> error::Result<day1::Vocabulary> {
+    let file = File::open(path).map_err(|source| error::Error::Io { line: None, source })?;
+    day1::Vocabulary::from_lines(&solution::read_lines(BufReader::new(file))?)
+}
+
+fn open_or_exit(day: u8, input: Option<PathBuf>) -> Box<dyn BufRead> {
+    let path = input.unwrap_or_else(|| runner::default_input(day));
+    match runner::open_input(&path) {
+        Ok(buf) => buf,
+        Err(err) => {
+            eprintln!("open file {}: {}", path.display(), err);
+            process::exit(1);
+        }
     }
 }
 
-fn read_lines<P>(filename: P) -> io::Result<io::BufReader<File>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file))
+// day 2 input, checked against the colors of `bag`
+fn games_or_exit(input: Option<PathBuf>, bag: &day2::Sample) -> Vec<day2::Game> {
+    let games = solution::read_lines(open_or_exit(2, input))
+        .and_then(|lines| day2::parse_games(&lines, bag));
+    match games {
+        Ok(games) => games,
+        Err(err) => {
+            eprintln!("day 2: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_or_exit<S: Solution>(day: u8, input: Option<PathBuf>) -> S::Input {
+    match S::parse(open_or_exit(day, input)) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("day {}: {}", day, err);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::answers::{Answers, Status};
+use crate::error::Result;
+use crate::solution::{self, Answer, DynSolution};
+
+pub struct Run {
+    pub answer: Result<Answer>,
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+
+pub fn run(solution: &dyn DynSolution, part: u8, buf: &mut dyn BufRead) -> Run {
+    let start = Instant::now();
+    let input = solution.parse(buf);
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let answer = input.and_then(|input| solution.solve(part, input.as_ref()));
+    let solve_time = start.elapsed();
+
+    Run {
+        answer,
+        parse_time,
+        solve_time,
+    }
+}
+
+pub fn default_input(day: u8) -> PathBuf {
+    PathBuf::from(format!("./input/day{}.txt", day))
+}
+
+// "-" reads the puzzle input from stdin
+pub fn open_input<P>(filename: P) -> io::Result<Box<dyn BufRead>>
+where
+    P: AsRef<Path>,
+{
+    if filename.as_ref() == Path::new("-") {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+
+    let file = File::open(filename)?;
+    Ok(Box::new(io::BufReader::new(file)))
+}
+
+// runs the embedded examples of a part and returns whether all of them passed
+pub fn run_examples(solution: &dyn DynSolution, part: u8) -> bool {
+    let mut passed = true;
+
+    for example in solution.examples().iter().filter(|e| e.part == part) {
+        let run = run(solution, part, &mut example.input.as_bytes());
+        match run.answer {
+            Ok(answer) if answer == example.answer => println!("{} PASS", answer),
+            Ok(answer) => {
+                passed = false;
+                println!("{} FAIL (expected {})", answer, example.answer);
+            }
+            Err(err) => {
+                passed = false;
+                println!("error: {}", err);
+            }
+        }
+    }
+
+    passed
+}
+
+// runs every registered day and part, prints a table and returns the number of
+// parts that failed or didn't match the stored answer
+pub fn run_all() -> usize {
+    let mut failed = 0;
+
+    println!(
+        "{:>3} {:>4}  {:>20}  {:>12}  {:>12}  status",
+        "day", "part", "answer", "parse", "solve"
+    );
+
+    for (day, solution) in solution::REGISTRY {
+        let path = default_input(*day);
+        let answers = match Answers::load(*day) {
+            Ok(answers) => answers,
+            Err(err) => {
+                failed += 1;
+                println!("{:>3}       answers: {}", day, err);
+                continue;
+            }
+        };
+
+        for part in solution::PARTS {
+            let mut buf = match open_input(&path) {
+                Ok(buf) => buf,
+                Err(err) => {
+                    println!(
+                        "{:>3} {:>4}  skipped: {}: {}",
+                        day,
+                        part,
+                        path.display(),
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            let run = run(*solution, part, &mut buf);
+            let (answer, status) = match &run.answer {
+                Ok(answer) => {
+                    let status = answers.check(part, *answer);
+                    if let Status::Fail(_) = status {
+                        failed += 1;
+                    }
+                    (answer.to_string(), status.to_string())
+                }
+                Err(err) => {
+                    failed += 1;
+                    (format!("error: {}", err), "ERROR".to_string())
+                }
+            };
+
+            println!(
+                "{:>3} {:>4}  {:>20}  {:>12}  {:>12}  {}",
+                day,
+                part,
+                answer,
+                format!("{:.2?}", run.parse_time),
+                format!("{:.2?}", run.solve_time),
+                status
+            );
+        }
+    }
+
+    failed
+}
@@ -0,0 +1,151 @@
+use std::any::Any;
+use std::io::BufRead;
+
+use crate::error::{Error, Result};
+use crate::{day1, day2, day3, day4, day5};
+
+pub type Answer = i64;
+
+// example from the puzzle text together with its known answer
+pub struct Example {
+    pub part: u8,
+    pub input: &'static str,
+    pub answer: Answer,
+}
+
+pub trait Solution {
+    type Input;
+
+    const EXAMPLES: &'static [Example];
+
+    fn parse<R: BufRead>(input: R) -> Result<Self::Input>;
+
+    fn part1(input: &Self::Input) -> Result<Answer>;
+
+    fn part2(input: &Self::Input) -> Result<Answer>;
+}
+
+// type-erased Solution, so that days with different inputs fit in one registry
+pub trait DynSolution: Sync {
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Box<dyn Any>>;
+
+    fn solve(&self, part: u8, input: &dyn Any) -> Result<Answer>;
+
+    fn examples(&self) -> &'static [Example];
+}
+
+impl<S> DynSolution for S
+where
+    S: Solution + Sync,
+    S::Input: 'static,
+{
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Box<dyn Any>> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn solve(&self, part: u8, input: &dyn Any) -> Result<Answer> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("input was parsed by another day");
+
+        match part {
+            1 => S::part1(input),
+            2 => S::part2(input),
+            _ => Err(Error::UnknownPart(part)),
+        }
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        S::EXAMPLES
+    }
+}
+
+pub const PARTS: [u8; 2] = [1, 2];
+
+pub static REGISTRY: &[(u8, &dyn DynSolution)] = &[
+    (1, &day1::Day1),
+    (2, &day2::Day2),
+    (3, &day3::Day3),
+    (4, &day4::Day4),
+    (5, &day5::Day5),
+];
+
+pub fn get(day: u8) -> Option<&'static dyn DynSolution> {
+    REGISTRY
+        .iter()
+        .find(|(registered, _)| *registered == day)
+        .map(|(_, solution)| *solution)
+}
+
+pub fn days() -> impl Iterator<Item = u8> {
+    REGISTRY.iter().map(|(day, _)| *day)
+}
+
+pub fn read_lines<R: BufRead>(input: R) -> Result<Vec<String>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.map_err(|source| Error::Io {
+                line: Some(i + 1),
+                source,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub fn check_examples<S: Solution>() {
+    for example in S::EXAMPLES {
+        let input = S::parse(example.input.as_bytes()).unwrap();
+        let answer = match example.part {
+            1 => S::part1(&input),
+            2 => S::part2(&input),
+            part => Err(Error::UnknownPart(part)),
+        };
+
+        assert_eq!(
+            answer.unwrap(),
+            example.answer,
+            "part {} example:\n{}",
+            example.part,
+            example.input
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_from_str() {
+        let solution = get(1).unwrap();
+        let input = solution
+            .parse(&mut "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n".as_bytes())
+            .unwrap();
+
+        assert_eq!(solution.solve(1, input.as_ref()).unwrap(), 142);
+        assert!(solution.solve(3, input.as_ref()).is_err());
+    }
+
+    #[test]
+    fn every_part_has_example() {
+        for (day, solution) in REGISTRY {
+            for part in PARTS {
+                assert!(
+                    solution.examples().iter().any(|e| e.part == part),
+                    "day {} part {} has no example",
+                    day,
+                    part
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_day() {
+        assert!(get(0).is_none());
+        assert!(get(26).is_none());
+    }
+}